@@ -0,0 +1,47 @@
+use crate::error::{Result, RevolveError};
+
+/// Maps a Rust target triple (e.g. `aarch64-unknown-linux-gnu`) to the RPM architecture name
+/// used in package file names and passed to `rpmbuild --target`.
+pub fn rpm_arch_for_target(triple: &str) -> Result<&'static str> {
+  let cpu = triple.split('-').next().unwrap_or_default();
+  let hard_float = triple.ends_with("hf");
+
+  let arch = match cpu {
+    "x86_64" => "x86_64",
+    "i686" => "i686",
+    "i586" => "i586",
+    "aarch64" => "aarch64",
+    "armv7" | "thumbv7neon" if hard_float => "armv7hl",
+    "armv7" | "thumbv7neon" => "armv7l",
+    "arm" if hard_float => "armv6hl",
+    "arm" | "armv5te" => "armv5tel",
+    "powerpc64le" => "ppc64le",
+    "powerpc64" => "ppc64",
+    "powerpc" => "ppc",
+    "s390x" => "s390x",
+    "riscv64gc" | "riscv64" => "riscv64",
+    "loongarch64" => "loongarch64",
+    "mips64el" => "mips64el",
+    "mipsel" => "mipsel",
+    _ => {
+      return Err(RevolveError::Config(format!(
+        "Cannot map target triple `{}` to an RPM architecture",
+        triple
+      )));
+    }
+  };
+  Ok(arch)
+}
+
+/// The RPM architecture of the machine this tool was compiled for, used when no target is given.
+pub fn host_rpm_arch() -> &'static str {
+  match std::env::consts::ARCH {
+    "x86" => "i686",
+    "arm" => "armv7hl",
+    "powerpc64" if cfg!(target_endian = "little") => "ppc64le",
+    "powerpc64" => "ppc64",
+    "powerpc" => "ppc",
+    "riscv64" => "riscv64",
+    other => other,
+  }
+}
@@ -1,71 +1,440 @@
-use crate::config::{Asset, BuildCommand, RevolveConfig};
-use crate::definitions::{BuilderContext, PkgContext, TemplateContext};
-use crate::error::Result;
-
-use std::collections::{HashMap, HashSet};
+use crate::arch;
+use crate::compression::{self, ArchiveEncoder};
+use crate::elf;
+use crate::config::{Asset, AssetKind, BuildCommand, CompressionFormat, Dependency, RevolveConfig};
+use crate::definitions::{
+  BuilderContext, PkgContext, ScriptsContext, SubpackageContext, SysusersContext, SystemdContext,
+  TemplateContext,
+};
+use crate::error::{Result, RevolveError, chain_message};
+use crate::native;
+use crate::report::{ArtifactReport, BuildReport, CommandReport, ErrorReport, VerificationReport};
+use crate::systemd;
+use crate::sysusers;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
 use std::fs;
-use std::io::{BufRead, BufReader};
-use std::path::{Path, PathBuf};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Component, Path, PathBuf};
 use std::process::{Command, ExitStatus};
 use std::thread;
+use std::time::Instant;
 
-use anyhow::{Context, bail};
-use cargo_metadata::Package as CargoPackage;
-use flate2::Compression;
-use flate2::write::GzEncoder;
+use anyhow::Context;
+use cargo_metadata::{Metadata, Package as CargoPackage};
 use rpm::Package as RpmPackage;
-use tar::Builder;
+use sha2::{Digest, Sha256};
+use tar::{Builder, Header, HeaderMode};
 use tera::Tera;
-use walkdir::WalkDir;
+use globset::{Glob, GlobBuilder, GlobSetBuilder};
+use ignore::WalkBuilder;
+
+/// Selects the tool that assembles the final binary RPM.
+#[derive(clap::ValueEnum, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+  /// Render the .spec template and hand it to the system `rpmbuild`.
+  #[default]
+  Rpmbuild,
+  /// Write the RPM directly with the `rpm` crate. Does not need `rpmbuild` installed.
+  Native,
+}
 
-/// The main entry point for the `build` command.
-pub fn run(
-  config: &RevolveConfig,
-  package: &CargoPackage,
-  target_dir: &Path,
+/// How `build` reports what it did.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+  /// Progress messages for people.
+  #[default]
+  Human,
+  /// Stdout is left to the caller: progress messages and the output of the build commands go
+  /// to stderr. [`build_workspace`] prints a JSON [`BuildReport`] per package there, one per
+  /// line.
+  Json,
+}
+
+/// `println!`, or `eprintln!` when the `MessageFormat` leaves stdout to the caller.
+macro_rules! status {
+  ($format:expr, $($arg:tt)*) => {
+    if $format == MessageFormat::Json {
+      eprintln!($($arg)*);
+    } else {
+      println!($($arg)*);
+    }
+  };
+}
+
+/// Flags for a `build` invocation, shared by every package being built.
+///
+/// Start from [`BuildOptions::new`], a plain build with the `rpmbuild` backend, and change what
+/// you need:
+///
+/// ```no_run
+/// use cargo_revolve::{Backend, BuildOptions};
+///
+/// let options = BuildOptions::new().backend(Backend::Native).verify(true);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
   dry_run: bool,
   no_archive: bool,
   verify: bool,
-) -> Result<()> {
-  // 1. Environment Check
-  check_environment()?;
+  backend: Backend,
+  target: Option<String>,
+  reproducible: bool,
+  message_format: MessageFormat,
+}
+
+impl BuildOptions {
+  /// A plain build with the `rpmbuild` backend that prints its progress to stdout.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Perform all steps except compiling and assembling the RPM, and print what would be done.
+  pub fn dry_run(mut self, dry_run: bool) -> Self {
+    self.dry_run = dry_run;
+    self
+  }
+
+  /// Build directly from the source tree without creating a source archive.
+  pub fn no_archive(mut self, no_archive: bool) -> Self {
+    self.no_archive = no_archive;
+    self
+  }
+
+  /// Check the built RPMs against the configuration.
+  pub fn verify(mut self, verify: bool) -> Self {
+    self.verify = verify;
+    self
+  }
+
+  /// How the RPM is assembled: by `rpmbuild` from the rendered .spec file, or natively.
+  pub fn backend(mut self, backend: Backend) -> Self {
+    self.backend = backend;
+    self
+  }
+
+  /// A Rust target triple to cross-compile for. Overrides the `target` config key.
+  pub fn target(mut self, target: Option<String>) -> Self {
+    self.target = target;
+    self
+  }
+
+  /// Normalize timestamps, ownership and ordering so identical inputs give identical outputs.
+  pub fn reproducible(mut self, reproducible: bool) -> Self {
+    self.reproducible = reproducible;
+    self
+  }
+
+  /// Where progress messages go. Use [`MessageFormat::Json`] to keep stdout free for your own
+  /// output; the report is returned either way.
+  pub fn message_format(mut self, message_format: MessageFormat) -> Self {
+    self.message_format = message_format;
+    self
+  }
+}
+
+/// The result of building one package as part of a multi-package build.
+struct PackageOutcome {
+  name: String,
+  version: String,
+  result: Result<BuildReport>,
+}
+
+/// Which packages of the workspace [`build_workspace`] builds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageSelection {
+  /// The package whose `Cargo.toml` is in the current directory.
+  Current,
+  /// Every workspace member with a `[package.metadata.revolve]` table.
+  Workspace,
+  /// The named workspace members, in this order.
+  Packages(Vec<String>),
+}
+
+/// What [`build_workspace`] did.
+pub struct WorkspaceBuild {
+  /// The report of every package that was attempted, including the ones that failed.
+  pub reports: Vec<BuildReport>,
+  /// The error of a failed single-package build, or a [`RevolveError::Packages`] summary when
+  /// packages of a multi-package build failed.
+  pub result: Result<()>,
+}
+
+/// Everything resolved for one package (expanded assets, subpackages, architecture) before it is
+/// handed to a backend.
+pub struct PackagePlan<'a> {
+  /// The configuration with its `assets` already expanded.
+  pub(crate) config: RevolveConfig,
+  pub(crate) package: &'a CargoPackage,
+  pub(crate) target_dir: &'a Path,
+  pub(crate) created_dirs: Option<Vec<String>>,
+  pub(crate) subpackages: Vec<SubpackageContext>,
+  /// Shared library requirements detected from the main package's binaries.
+  pub(crate) auto_requires: Vec<Dependency>,
+  /// The contents of the configured scriptlet files.
+  pub(crate) scripts: ScriptsContext,
+  pub(crate) systemd: SystemdContext,
+  pub(crate) sysusers: SysusersContext,
+  /// The RPM architecture of the packaged binaries, e.g. `x86_64`.
+  pub(crate) rpm_arch: &'static str,
+  pub(crate) target_triple: Option<String>,
+  /// The timestamp used for every file and the build time in `--reproducible` mode. RPM
+  /// timestamps are 32 bits wide.
+  pub(crate) source_date_epoch: Option<u32>,
+  /// Where the progress messages of the build and verification go.
+  pub(crate) message_format: MessageFormat,
+  /// The backend that assembles the RPM. Only `rpmbuild` strips binaries, so only its
+  /// packages may hold binaries that differ from their sources.
+  pub(crate) backend: Backend,
+}
+
+impl<'a> PackagePlan<'a> {
+  /// Resolves and expands `config` for `package` without compiling anything.
+  ///
+  /// Directory and glob assets are expanded from what is on disk now, and shared library
+  /// requirements are read from the binaries that are already built.
+  pub fn new(
+    config: &RevolveConfig,
+    package: &'a CargoPackage,
+    target_dir: &'a Path,
+    options: &BuildOptions,
+  ) -> Result<Self> {
+    let (target_triple, rpm_arch) = target_arch(config, options)?;
+    let source_date_epoch = source_date_epoch(package, options)?;
+    let resolved = resolve_config(config, package, target_dir)?;
+    Self::from_resolved(
+      resolved,
+      package,
+      target_dir,
+      target_triple,
+      rpm_arch,
+      source_date_epoch,
+      options,
+    )
+  }
+
+  /// Expands a resolved configuration and detects the shared library requirements.
+  fn from_resolved(
+    resolved: ResolvedConfig,
+    package: &'a CargoPackage,
+    target_dir: &'a Path,
+    target_triple: Option<String>,
+    rpm_arch: &'static str,
+    source_date_epoch: Option<u32>,
+    options: &BuildOptions,
+  ) -> Result<Self> {
+    let ResolvedConfig {
+      config,
+      scripts,
+      systemd,
+      sysusers,
+    } = resolved;
+    let manifest_dir = package.manifest_path.parent().unwrap().as_std_path();
+
+    let ExpandedConfig {
+      config: expanded_config,
+      created_dirs,
+      mut subpackages,
+    } = expand_config(&config, package, target_dir, target_triple.as_deref())?;
+
+    // Detect the shared libraries the freshly built binaries link against.
+    log::info!("Scanning binaries for shared library requirements...");
+    let auto_requires_config = config.auto_requires.clone().unwrap_or_default();
+    let auto_requires = elf::auto_requires(
+      expanded_config.assets.as_deref().unwrap_or_default(),
+      &auto_requires_config,
+      manifest_dir,
+      target_dir,
+    )?;
+    for sub in &mut subpackages {
+      sub.auto_requires =
+        elf::auto_requires(&sub.assets, &auto_requires_config, manifest_dir, target_dir)?;
+    }
+    Ok(PackagePlan {
+      config: expanded_config,
+      package,
+      target_dir,
+      created_dirs,
+      subpackages,
+      auto_requires,
+      scripts,
+      systemd,
+      sysusers,
+      rpm_arch,
+      target_triple,
+      source_date_epoch,
+      message_format: options.message_format,
+      backend: options.backend,
+    })
+  }
+
+  /// The configuration with its `assets` expanded into one entry per file.
+  pub fn config(&self) -> &RevolveConfig {
+    &self.config
+  }
+
+  pub fn package(&self) -> &CargoPackage {
+    self.package
+  }
+
+  /// The RPM architecture of the packaged binaries, e.g. `x86_64`.
+  pub fn rpm_arch(&self) -> &'static str {
+    self.rpm_arch
+  }
+
+  /// The directories the main package owns through `%dir`.
+  pub fn created_dirs(&self) -> &[String] {
+    self.created_dirs.as_deref().unwrap_or_default()
+  }
+
+  pub fn manifest_dir(&self) -> &Path {
+    self.package.manifest_path.parent().unwrap().as_std_path()
+  }
+}
 
-  execute_build_process(config, package, target_dir, dry_run)?;
+/// The target triple to build for and its RPM architecture. The CLI `--target` wins over the
+/// `target` key in Cargo.toml.
+fn target_arch(
+  config: &RevolveConfig,
+  options: &BuildOptions,
+) -> Result<(Option<String>, &'static str)> {
+  let target_triple = options.target.clone().or_else(|| config.target.clone());
+  let rpm_arch = match &target_triple {
+    Some(triple) => arch::rpm_arch_for_target(triple)?,
+    None => arch::host_rpm_arch(),
+  };
+  log::debug!("Target triple: {:?}, RPM architecture: {}", target_triple, rpm_arch);
+  Ok((target_triple, rpm_arch))
+}
 
+/// The timestamp of a `--reproducible` build, if this is one.
+fn source_date_epoch(package: &CargoPackage, options: &BuildOptions) -> Result<Option<u32>> {
+  if !options.reproducible {
+    return Ok(None);
+  }
   let manifest_dir = package.manifest_path.parent().unwrap().as_std_path();
+  let epoch = resolve_source_date_epoch(manifest_dir)?;
+  log::info!("Reproducible build with SOURCE_DATE_EPOCH={}", epoch);
+  Ok(Some(epoch))
+}
 
-  // Create a mutable copy of the config so we can replace the assets list.
-  let mut mutable_config = config; // This is a reference, not a deep clone.
-  let mut _expanded_assets_config: Option<RevolveConfig> = None;
-  let mut created_dirs: Option<Vec<String>> = None;
+/// The report of a package whose build failed with `error`. The architecture is the one of
+/// `--target` or of the `target` key in `config`, as in [`target_arch`], or of the host.
+/// `config` is `None` when the package's configuration could not be read.
+fn failed_report(
+  package: &CargoPackage,
+  config: Option<&RevolveConfig>,
+  options: &BuildOptions,
+  error: &RevolveError,
+) -> BuildReport {
+  let rpm_arch = options
+    .target
+    .as_deref()
+    .or_else(|| config.and_then(|config| config.target.as_deref()))
+    .and_then(|triple| arch::rpm_arch_for_target(triple).ok())
+    .unwrap_or_else(arch::host_rpm_arch);
+  BuildReport {
+    name: package.name.to_string(),
+    version: package.version.to_string(),
+    release: None,
+    arch: rpm_arch.to_string(),
+    backend: options.backend,
+    dry_run: options.dry_run,
+    spec: None,
+    archive: None,
+    commands: Vec::new(),
+    artifacts: Vec::new(),
+    verification: None,
+    error: Some(ErrorReport {
+      exit_code: error.exit_code(),
+      message: chain_message(error),
+    }),
+  }
+}
 
-  if let Some(initial_assets) = &config.assets {
-      log::info!("Expanding directory assets...");
-      // Capture both the files and the directories.
-      let (final_assets, dirs) = expand_assets(initial_assets, manifest_dir)?;
-      created_dirs = Some(dirs); // Store the discovered directories.
-      log::info!(
-          "Asset expansion complete. Found {} file assets and {} unique directories.", 
-          final_assets.len(),
-          created_dirs.as_ref().unwrap().len()
-      );
+/// Builds the RPMs of one package: compiles it, expands its assets and assembles the RPMs with
+/// the selected backend, then copies them to the `output_dir`.
+/// Returns what was built (no artifacts on a dry run), and fails if `--verify` found a problem.
+pub fn run(
+  config: &RevolveConfig,
+  package: &CargoPackage,
+  target_dir: &Path,
+  options: &BuildOptions,
+) -> Result<BuildReport> {
+  let report = build_package(config, package, target_dir, options)?;
+  report.check_verification()?;
+  Ok(report)
+}
 
-    // Create a new owned RevolveConfig with the expanded assets.
-    // This is necessary because `config` is a borrowed reference.
-    _expanded_assets_config = Some(RevolveConfig {
-      spec_template: config.spec_template.clone(),
-      output_dir: config.output_dir.clone(),
-      changelog: config.changelog.clone(),
-      build_flags: config.build_flags.clone(),
-      assets: Some(final_assets), // Use the new expanded list.
-      verify_license: config.verify_license.clone(),
-      verify_summary: config.verify_summary.clone(),
-      build_command: config.build_command.clone(), // You will need to derive Clone for BuildCommand
-    });
-    // Point our mutable_config to the new, owned config struct.
-    mutable_config = _expanded_assets_config.as_ref().unwrap();
+/// Like [`run`], but a failed verification is only recorded in the report, so the report of a
+/// package that was built but does not match its configuration is not lost.
+pub fn build_package(
+  config: &RevolveConfig,
+  package: &CargoPackage,
+  target_dir: &Path,
+  options: &BuildOptions,
+) -> Result<BuildReport> {
+  let BuildOptions {
+    dry_run,
+    verify,
+    backend,
+    message_format,
+    ..
+  } = *options;
+
+  let (target_triple, rpm_arch) = target_arch(config, options)?;
+
+  // 1. Environment Check
+  // `rpmbuild` is only needed when we are actually going to invoke it.
+  if backend == Backend::Rpmbuild && !dry_run {
+    check_environment()?;
+  }
+
+  let manifest_dir = package.manifest_path.parent().unwrap().as_std_path();
+  let source_date_epoch = source_date_epoch(package, options)?;
+  let mut report = BuildReport {
+    name: package.name.to_string(),
+    version: package.version.to_string(),
+    release: None,
+    arch: rpm_arch.to_string(),
+    backend,
+    dry_run,
+    spec: None,
+    archive: None,
+    commands: Vec::new(),
+    artifacts: Vec::new(),
+    verification: None,
+    error: None,
+  };
+
+  // Mistakes in the configuration are reported before the (possibly long) compilation.
+  let resolved = resolve_config(config, package, target_dir)?;
+  execute_build_process(
+    &resolved.config,
+    package,
+    target_dir,
+    target_triple.as_deref(),
+    rpm_arch,
+    options,
+    &mut report.commands,
+  )?;
+
+  // Directory and glob assets may match build artifacts, so they are expanded afterwards.
+  let plan = PackagePlan::from_resolved(
+    resolved,
+    package,
+    target_dir,
+    target_triple,
+    rpm_arch,
+    source_date_epoch,
+    options,
+  )?;
+  // A dry run leaves the target directory alone.
+  if !dry_run {
+    sysusers::write_file(&plan.sysusers)?;
   }
-  // All subsequent code will now use `mutable_config` which has the expanded asset list.
 
   let revolve_dir = manifest_dir.join("target/revolve");
 
@@ -96,29 +465,416 @@ pub fn run(
     )
   })?;
 
-  // 4. Create source archive
+  // 4. Assemble the RPM with the selected backend.
+  match backend {
+    Backend::Rpmbuild => {
+      build_with_rpmbuild(&plan, &build_dir, &rpmbuild_dir, options, &mut report)?
+    }
+    Backend::Native => {
+      build_with_native(&plan, &rpmbuild_dir, dry_run)?;
+      // Only the rpmbuild backend takes the release from the .spec file.
+      report.release = Some(native::release(&plan).to_string());
+    }
+  }
+
+  if !dry_run {
+    // 6. Collect artifacts
+    let artifacts =
+      collect_artifacts(&rpmbuild_dir, &plan.config.output_dir, manifest_dir, message_format)?;
+    report.artifacts = artifacts
+      .iter()
+      .map(|path| ArtifactReport::read(path))
+      .collect::<Result<_>>()?;
+    if let Some(main) = report.artifacts.iter().find(|artifact| {
+      artifact.name == report.name && !artifact.path.to_string_lossy().ends_with(".src.rpm")
+    }) {
+      report.release = Some(main.release.clone());
+      report.arch = main.arch.clone();
+    }
+
+    if verify {
+      log::info!("--verify flag is set, verifying package contents...");
+      report.verification = Some(verify_artifacts(&plan, &artifacts));
+    }
+  }
+  Ok(report)
+}
+
+/// What each package of `plan` is expected to contain.
+fn expected_packages<'p>(plan: &'p PackagePlan) -> Vec<ExpectedPackage<'p>> {
+  let config = &plan.config;
+  let mut expected_packages = vec![ExpectedPackage {
+    name: &plan.package.name,
+    license: config.verify_license.as_deref(),
+    summary: config.verify_summary.as_deref(),
+    assets: config.assets.as_deref(),
+    requires: config.requires.as_deref().unwrap_or_default(),
+    provides: config.provides.as_deref().unwrap_or_default(),
+    conflicts: config.conflicts.as_deref().unwrap_or_default(),
+    obsoletes: config.obsoletes.as_deref().unwrap_or_default(),
+    scripts: Some(&plan.scripts),
+  }];
+  expected_packages.extend(plan.subpackages.iter().map(|sub| ExpectedPackage {
+    name: &sub.full_name,
+    license: config.verify_license.as_deref(),
+    summary: sub.summary.as_deref(),
+    assets: Some(&sub.assets),
+    requires: &sub.requires,
+    provides: &[],
+    conflicts: &[],
+    obsoletes: &[],
+    scripts: None,
+  }));
+  expected_packages
+}
+
+/// Verifies the binary RPM of every package of `plan` among the built `artifacts`.
+fn verify_artifacts(plan: &PackagePlan, artifacts: &[PathBuf]) -> Vec<VerificationReport> {
+  let version = plan.package.version.to_string();
+  expected_packages(plan)
+    .iter()
+    .map(|expected| {
+      // Find the binary RPM for this package instead of just taking the first one.
+      let expected_binary_rpm_prefix = format!("{}-{}-", expected.name, version);
+
+      let binary_rpm = artifacts.iter().find(|path| {
+        let filename = path.file_name().unwrap_or_default().to_string_lossy();
+        filename.starts_with(&expected_binary_rpm_prefix)
+          && !filename.contains("debuginfo")
+          && !filename.contains("debugsource")
+          && !filename.contains(".src.rpm") // Also exclude source RPMs explicitly
+      });
+
+      let result = match binary_rpm {
+        Some(rpm_path) => check_package(plan, rpm_path, expected),
+        // Provide a helpful error if we built RPMs but couldn't find this one.
+        None => Err(RevolveError::Verification(format!(
+          "Verification failed: Could not find the binary RPM for '{}' to verify. Found artifacts: {:?}",
+          expected.name,
+          artifacts
+        ))),
+      };
+      VerificationReport {
+        package: expected.name.to_string(),
+        rpm: binary_rpm.cloned(),
+        passed: result.is_ok(),
+        error: result.err().map(|e| chain_message(&e)),
+      }
+    })
+    .collect()
+}
+
+/// The configuration of one package with the assets generated for its systemd units and system
+/// users added, along with the files read to generate them.
+pub(crate) struct ResolvedConfig {
+  pub config: RevolveConfig,
+  pub scripts: ScriptsContext,
+  pub systemd: SystemdContext,
+  pub sysusers: SysusersContext,
+}
+
+/// Checks everything that can be checked before compiling, so mistakes are reported right away.
+pub(crate) fn resolve_config(
+  config: &RevolveConfig,
+  package: &CargoPackage,
+  target_dir: &Path,
+) -> Result<ResolvedConfig> {
+  let manifest_dir = package.manifest_path.parent().unwrap().as_std_path();
+  validate_compression(config)?;
+
+  let scripts = read_scripts(config, manifest_dir)?;
+
+  // The unit files and the generated sysusers.d file are installed like any other asset.
+  let unit_sources = config.systemd_units.as_deref().unwrap_or_default();
+  let systemd = systemd::plan_units(unit_sources, manifest_dir)?;
+  let users = config.users.as_deref().unwrap_or_default();
+  let sysusers = sysusers::plan_users(&package.name, users, target_dir)?;
+  let config = if unit_sources.is_empty() && users.is_empty() {
+    config.clone()
+  } else {
+    let mut assets = config.assets.clone().unwrap_or_default();
+    assets.extend(systemd::unit_assets(unit_sources, &systemd));
+    assets.extend(sysusers::sysusers_asset(&sysusers));
+    // Both `%systemd_*` and `%sysusers_create_compat` come from systemd-rpm-macros.
+    let mut build_requires = config.build_requires.clone().unwrap_or_default();
+    systemd::add_macros_build_requirement(&mut build_requires);
+    RevolveConfig {
+      assets: Some(assets),
+      build_requires: Some(build_requires),
+      ..config.clone()
+    }
+  };
+
+  Ok(ResolvedConfig {
+    config,
+    scripts,
+    systemd,
+    sysusers,
+  })
+}
+
+/// A configuration whose `assets` are expanded into one entry per file.
+pub(crate) struct ExpandedConfig {
+  pub config: RevolveConfig,
+  pub created_dirs: Option<Vec<String>>,
+  pub subpackages: Vec<SubpackageContext>,
+}
+
+/// Expands the directory and glob assets of the main package and its subpackages.
+pub(crate) fn expand_config(
+  config: &RevolveConfig,
+  package: &CargoPackage,
+  target_dir: &Path,
+  target_triple: Option<&str>,
+) -> Result<ExpandedConfig> {
+  let manifest_dir = package.manifest_path.parent().unwrap().as_std_path();
+  let mut expanded = config.clone();
+  let mut created_dirs = None;
+
+  if let Some(initial_assets) = &config.assets {
+    // Cross-compiled artifacts live under `target/<triple>/`, so point the assets there.
+    let initial_assets = match target_triple {
+      Some(triple) => retarget_assets(initial_assets, triple, config.build_flags.as_deref()),
+      None => initial_assets.clone(),
+    };
+
+    log::info!("Expanding directory assets...");
+    let (final_assets, dirs) = expand_assets(&initial_assets, manifest_dir, target_dir)?;
+    log::info!(
+      "Asset expansion complete. Found {} file assets and {} unique directories.",
+      final_assets.len(),
+      dirs.len()
+    );
+    expanded.assets = Some(final_assets);
+    created_dirs = Some(dirs);
+  }
+
+  let subpackages = expand_subpackages(
+    config,
+    package,
+    manifest_dir,
+    target_dir,
+    target_triple,
+    expanded.assets.as_deref().unwrap_or_default(),
+  )?;
+
+  Ok(ExpandedConfig {
+    config: expanded,
+    created_dirs,
+    subpackages,
+  })
+}
+
+/// Prints a summary table for a multi-package build and fails if any package failed.
+/// All failures are reported together so one broken package does not hide the others.
+fn report_outcomes(outcomes: &[PackageOutcome], message_format: MessageFormat) -> Result<()> {
+  let name_width = outcomes
+    .iter()
+    .map(|o| o.name.len())
+    .chain(std::iter::once("PACKAGE".len()))
+    .max()
+    .unwrap_or_default();
+  let version_width = outcomes
+    .iter()
+    .map(|o| o.version.len())
+    .chain(std::iter::once("VERSION".len()))
+    .max()
+    .unwrap_or_default();
+
+  status!(message_format, "\nBuild summary:");
+  status!(
+    message_format,
+    "  {:<name_width$}  {:<version_width$}  {:<6}  RPMS",
+    "PACKAGE", "VERSION", "STATUS"
+  );
+  for outcome in outcomes {
+    let (status, rpms) = match &outcome.result {
+      Ok(report) => ("ok", report.artifacts.len().to_string()),
+      Err(_) => ("FAILED", "-".to_string()),
+    };
+    status!(
+      message_format,
+      "  {:<name_width$}  {:<version_width$}  {:<6}  {}",
+      outcome.name, outcome.version, status, rpms
+    );
+  }
+
+  let failures: Vec<&PackageOutcome> = outcomes.iter().filter(|o| o.result.is_err()).collect();
+  if failures.is_empty() {
+    return Ok(());
+  }
+
+  let mut exit_codes = HashSet::new();
+  for failure in &failures {
+    if let Err(e) = &failure.result {
+      exit_codes.insert(e.exit_code());
+      eprintln!("\nError building {}: {}", failure.name, chain_message(e));
+    }
+  }
+  Err(RevolveError::Packages {
+    failed: failures.len(),
+    total: outcomes.len(),
+    // Only a cause shared by every failure is worth reporting.
+    exit_code: match exit_codes.len() {
+      1 => exit_codes.into_iter().next().unwrap(),
+      _ => 1,
+    },
+  })
+}
+
+/// Builds the selected packages the way `cargo revolve build` does. A failed package does not
+/// stop the others; its report only holds what was known before its build started.
+///
+/// With [`MessageFormat::Json`] every report is printed to stdout as one line of JSON. Building
+/// more than one package ends with a summary table.
+pub fn build_workspace(
+  metadata: &Metadata,
+  selection: &PackageSelection,
+  options: &BuildOptions,
+) -> Result<WorkspaceBuild> {
+  let target_dir = metadata.target_directory.as_std_path();
+  let message_format = options.message_format;
+  let selected = select_packages(metadata, selection)?;
+
+  let mut reports = Vec::new();
+  let result = if *selection == PackageSelection::Current {
+    build_member(selected[0], target_dir, options, &mut reports).map(|_| ())
+  } else {
+    let mut outcomes = Vec::new();
+    for package in selected {
+      status!(message_format, "\n=== Building {} {} ===", package.name, package.version);
+      outcomes.push(PackageOutcome {
+        name: package.name.to_string(),
+        version: package.version.to_string(),
+        result: build_member(package, target_dir, options, &mut reports),
+      });
+    }
+    report_outcomes(&outcomes, message_format)
+  };
+  Ok(WorkspaceBuild { reports, result })
+}
+
+/// Builds one selected package for [`build_workspace`] and adds its report to `reports`, even
+/// when the build or its verification fails. With [`MessageFormat::Json`] the report of a
+/// failed build is printed too.
+fn build_member(
+  package: &CargoPackage,
+  target_dir: &Path,
+  options: &BuildOptions,
+  reports: &mut Vec<BuildReport>,
+) -> Result<BuildReport> {
+  let (config, built) = match RevolveConfig::from_manifest(package.manifest_path.as_std_path()) {
+    Ok(config) => {
+      let built = build_package(&config, package, target_dir, options);
+      (Some(config), built)
+    }
+    Err(e) => (None, Err(e)),
+  };
+  let (report, result) = match built {
+    Ok(report) => {
+      let verified = report.check_verification();
+      (report, verified)
+    }
+    Err(e) => (failed_report(package, config.as_ref(), options, &e), Err(e)),
+  };
+  if options.message_format == MessageFormat::Json {
+    print_json_report(&report)?;
+  }
+  reports.push(report.clone());
+  result.map(|()| report)
+}
+
+/// Prints `report` on one line of stdout for `--message-format json`.
+fn print_json_report(report: &BuildReport) -> Result<()> {
+  let json = serde_json::to_string(report).context("Failed to serialize the build report")?;
+  println!("{}", json);
+  Ok(())
+}
+
+/// The workspace members picked by `selection`. [`PackageSelection::Current`] gives exactly one
+/// package, and fails if there is no `Cargo.toml` in the current directory.
+pub fn select_packages<'a>(
+  metadata: &'a Metadata,
+  selection: &PackageSelection,
+) -> Result<Vec<&'a CargoPackage>> {
+  let members = metadata.workspace_packages();
+
+  match selection {
+    PackageSelection::Current => {
+      let current_dir = env::current_dir()?;
+      let package = metadata
+        .packages
+        .iter()
+        .find(|p| p.manifest_path.as_std_path().parent().unwrap() == current_dir)
+        .ok_or_else(|| {
+          RevolveError::Config("Could not find a Cargo.toml in the current directory".to_string())
+        })?;
+      log::debug!("Found manifest path: {}", package.manifest_path);
+      Ok(vec![package])
+    }
+    PackageSelection::Packages(names) => names
+      .iter()
+      .map(|name| {
+        members
+          .iter()
+          .find(|p| p.name.as_str() == name)
+          .copied()
+          .ok_or_else(|| {
+            RevolveError::Config(format!("Package `{}` is not a member of this workspace", name))
+          })
+      })
+      .collect(),
+    PackageSelection::Workspace => {
+      let selected: Vec<&CargoPackage> = members
+        .into_iter()
+        .filter(|p| p.metadata.get("revolve").is_some())
+        .collect();
+      if selected.is_empty() {
+        return Err(RevolveError::Config(
+          "No workspace member has a `[package.metadata.revolve]` table".to_string(),
+        ));
+      }
+      Ok(selected)
+    }
+  }
+}
+
+/// Creates the source archive, renders the .spec and runs `rpmbuild` (or describes it on a dry run).
+/// The files and the command are recorded in `report`.
+fn build_with_rpmbuild(
+  plan: &PackagePlan,
+  build_dir: &Path,
+  rpmbuild_dir: &Path,
+  options: &BuildOptions,
+  report: &mut BuildReport,
+) -> Result<()> {
+  let BuildOptions { dry_run, no_archive, .. } = *options;
+  let manifest_dir = plan.manifest_dir();
+  // Only ask rpmbuild to switch architectures when we are cross-compiling.
+  let rpm_target = plan.target_triple.as_ref().map(|_| plan.rpm_arch);
+
   let source_archive_path = if !no_archive {
-    Some(create_artifact_archive(
-      mutable_config, package, target_dir, dry_run,
-    )?)
+    Some(create_artifact_archive(plan, dry_run)?)
   } else {
     None
   };
 
-  let (rendered_spec_path, rendered_spec_content) = render_spec(mutable_config, package, &build_dir,
-    created_dirs)?;
+  let (rendered_spec_path, rendered_spec_content) = render_spec(plan, build_dir)?;
+  report.spec = Some(rendered_spec_path.clone());
+  let defines = rpmbuild_defines(plan);
 
   if dry_run {
-    println!("--- Dry Run Activated ---");
-    println!(
+    status!(plan.message_format, "--- Dry Run Activated ---");
+    status!(
+      plan.message_format,
       "\n[1/2] Rendered .spec file would be written to: {}",
       rendered_spec_path.display()
     );
-    println!("----------------------------------------------------");
-    println!("{}", rendered_spec_content);
-    println!("----------------------------------------------------");
+    status!(plan.message_format, "----------------------------------------------------");
+    status!(plan.message_format, "{}", rendered_spec_content);
+    status!(plan.message_format, "----------------------------------------------------");
 
-    let rpmbuild_command = if let Some(archive_path) = &source_archive_path {
+    let mut rpmbuild_command = if let Some(archive_path) = &source_archive_path {
       format!(
         "rpmbuild -ta {} --specfile {} --define='_topdir {}'",
         archive_path.display(),
@@ -133,71 +889,171 @@ pub fn run(
         manifest_dir.display() // Tell rpmbuild where to find the source
       )
     };
+    if let Some(arch) = rpm_target {
+      rpmbuild_command.push_str(&format!(" --target {}", arch));
+    }
+    if let Some(epoch) = plan.source_date_epoch {
+      rpmbuild_command = format!("SOURCE_DATE_EPOCH={} {}", epoch, rpmbuild_command);
+    }
+    for define in &defines {
+      rpmbuild_command.push_str(&format!(" --define='{}'", define));
+    }
 
-    println!("\n[2/2] The following `rpmbuild` command would be executed:");
-    println!("{}", rpmbuild_command);
-    println!("\n--- End of Dry Run ---");
-  } else {
-    // 5. Execute rpmbuild
-    execute_rpmbuild(
-      source_archive_path.as_deref(),
-      &rendered_spec_path,
-      &rpmbuild_dir,
-      manifest_dir,
-    )?;
-
-    // 6. Collect artifacts
-    let artifacts = collect_artifacts(&rpmbuild_dir, &mutable_config.output_dir, manifest_dir)?;
-    if verify {
-      log::info!("--verify flag is set, verifying package contents...");
+    status!(plan.message_format, "\n[2/2] The following `rpmbuild` command would be executed:");
+    status!(plan.message_format, "{}", rpmbuild_command);
+    status!(plan.message_format, "\n--- End of Dry Run ---");
+    return Ok(());
+  }
 
-      // Find the main binary RPM instead of just taking the first one.
-      let expected_binary_rpm_prefix = format!("{}-{}", package.name, package.version);
+  // 5. Execute rpmbuild
+  let rpmbuild_command = execute_rpmbuild(
+    plan,
+    source_archive_path.as_deref(),
+    &rendered_spec_path,
+    rpmbuild_dir,
+    rpm_target,
+    &defines,
+  )?;
+  report.commands.push(rpmbuild_command);
+  report.archive = source_archive_path;
+  Ok(())
+}
 
-      let main_binary_rpm = artifacts.iter().find(|path| {
-        let filename = path.file_name().unwrap_or_default().to_string_lossy();
-        filename.starts_with(&expected_binary_rpm_prefix)
-          && !filename.contains("debuginfo")
-          && !filename.contains("debugsource")
-          && !filename.contains(".src.rpm") // Also exclude source RPMs explicitly
-      });
+/// Writes the RPM with the `rpm` crate. No archive or .spec is needed on this path.
+fn build_with_native(plan: &PackagePlan, rpmbuild_dir: &Path, dry_run: bool) -> Result<()> {
+  let changelog = read_changelog(&plan.config, plan.manifest_dir());
 
-      if let Some(rpm_path) = main_binary_rpm {
-        verify_package(rpm_path, package, mutable_config)?;
-      } else {
-        // Provide a helpful error if we built RPMs but couldn't find the main one.
-        bail!(
-          "Verification failed: Could not find the main binary RPM to verify. Found artifacts: {:?}",
-          artifacts
-        );
+  if dry_run {
+    status!(plan.message_format, "--- Dry Run Activated ---");
+    status!(plan.message_format, "\n[1/2] The native backend would package the following entries:");
+    for dir in plan.created_dirs.iter().flatten() {
+      status!(plan.message_format, "  %dir {}", dir);
+    }
+    for asset in plan.config.assets.iter().flatten() {
+      status!(plan.message_format, "  {}", describe_entry(asset));
+    }
+    for (name, content) in plan.scripts.iter() {
+      status!(plan.message_format, "  %{} ({} lines)", name, content.lines().count());
+    }
+    for sub in &plan.subpackages {
+      status!(plan.message_format, "  [{}]", sub.full_name);
+      for dir in &sub.created_dirs {
+        status!(plan.message_format, "    %dir {}", dir);
+      }
+      for asset in &sub.assets {
+        status!(plan.message_format, "    {}", describe_entry(asset));
       }
     }
+    status!(
+      plan.message_format,
+      "\n[2/2] The {} RPM would be written below: {}",
+      plan.rpm_arch,
+      rpmbuild_dir.join("RPMS").display()
+    );
+    status!(plan.message_format, "\n--- End of Dry Run ---");
+    return Ok(());
   }
 
+  native::build_packages(plan, changelog.as_deref(), rpmbuild_dir)?;
   Ok(())
 }
 
+/// Describes an asset for the native dry-run listing.
+fn describe_entry(asset: &Asset) -> String {
+  match &asset.symlink_target {
+    Some(target) => format!("{} (symlink to {})", asset.dest, target),
+    None => format!("{} -> {}", asset.source, asset.dest),
+  }
+}
+
+/// The macros passed to `rpmbuild` in `--reproducible` mode: a fixed build host, and the build
+/// time and file mtimes taken from `SOURCE_DATE_EPOCH`.
+const REPRODUCIBLE_DEFINES: [&str; 3] = [
+  "_buildhost reproducible",
+  "use_source_date_epoch_as_buildtime 1",
+  "clamp_mtime_to_source_date_epoch 1",
+];
+
+/// The macros passed to `rpmbuild` on top of `_topdir`, for settings that would otherwise have to
+/// be repeated in every template.
+fn rpmbuild_defines(plan: &PackagePlan) -> Vec<String> {
+  let mut defines = Vec::new();
+  if plan.source_date_epoch.is_some() {
+    defines.extend(REPRODUCIBLE_DEFINES.iter().map(|define| define.to_string()));
+  }
+  if let Some(format) = plan.config.payload_compression {
+    let payload = compression::binary_payload_macro(format, plan.config.payload_compression_level);
+    defines.push(format!("_binary_payload {}", payload));
+  }
+  defines
+}
+
+/// Rejects compression levels that the configured formats do not support.
+fn validate_compression(config: &RevolveConfig) -> Result<()> {
+  compression::validate_level(
+    "archive_compression_level",
+    config.archive_compression.unwrap_or(CompressionFormat::Gzip),
+    config.archive_compression_level,
+  )?;
+  match config.payload_compression {
+    Some(format) => {
+      compression::validate_level("payload_compression_level", format, config.payload_compression_level)
+    }
+    None if config.payload_compression_level.is_some() => {
+      Err(RevolveError::Config(
+        "`payload_compression_level` needs `payload_compression` to be set as well.".to_string(),
+      ))
+    }
+    None => Ok(()),
+  }
+}
+
+/// The file name of the source archive, e.g. `my-app-1.0.0.tar.gz`.
+fn archive_filename(config: &RevolveConfig, package: &CargoPackage) -> String {
+  let format = config.archive_compression.unwrap_or(CompressionFormat::Gzip);
+  format!("{}-{}.{}", package.name, package.version, format.archive_extension())
+}
+
+/// Reads `SOURCE_DATE_EPOCH`, falling back to the timestamp of the last git commit.
+fn resolve_source_date_epoch(manifest_dir: &Path) -> Result<u32> {
+  if let Ok(value) = std::env::var("SOURCE_DATE_EPOCH") {
+    return value.trim().parse().map_err(|_| {
+      RevolveError::Config(format!(
+        "SOURCE_DATE_EPOCH must be a number of seconds, found '{}'",
+        value
+      ))
+    });
+  }
+
+  let output = Command::new("git")
+    .args(["log", "-1", "--format=%ct"])
+    .current_dir(manifest_dir)
+    .output();
+  match output {
+    Ok(output) if output.status.success() => {
+      let timestamp = String::from_utf8_lossy(&output.stdout);
+      log::debug!("SOURCE_DATE_EPOCH is unset, using the last commit time {}", timestamp.trim());
+      Ok(timestamp.trim().parse().with_context(|| {
+        format!("Unexpected commit timestamp '{}' from git", timestamp.trim())
+      })?)
+    }
+    _ => Err(RevolveError::Config(
+      "--reproducible needs a timestamp: set SOURCE_DATE_EPOCH or build from a git checkout."
+        .to_string(),
+    )),
+  }
+}
+
 fn check_environment() -> Result<()> {
   log::info!("Checking for 'rpmbuild' executable...");
-  which::which("rpmbuild").context(
-    "'rpmbuild' command not found. Please ensure it is installed and in your system's PATH.",
-  )?;
+  which::which("rpmbuild").map_err(RevolveError::RpmbuildNotFound)?;
   log::info!("'rpmbuild' found.");
   Ok(())
 }
 
-fn render_spec(
-  config: &RevolveConfig,
-  package: &CargoPackage,
-  build_dir: &Path,
-  created_dirs: Option<Vec<String>>, 
-) -> Result<(PathBuf, String)> {
-  log::info!("Rendering .spec template...");
-  let manifest_dir = package.manifest_path.parent().unwrap().as_std_path();
-  let template_path = manifest_dir.join(&config.spec_template);
-
-  // Read changelog content if configured.
-  let changelog_content = if let Some(changelog_file) = &config.changelog {
+/// Reads the configured changelog file, if any.
+fn read_changelog(config: &RevolveConfig, manifest_dir: &Path) -> Option<String> {
+  if let Some(changelog_file) = &config.changelog {
     let changelog_path = manifest_dir.join(changelog_file);
     log::info!("Reading changelog from {}", changelog_path.display());
     match fs::read_to_string(&changelog_path) {
@@ -214,19 +1070,90 @@ fn render_spec(
     }
   } else {
     None
+  }
+}
+
+/// Reads the files named in the `scripts` table. Unlike the changelog, a missing scriptlet is an
+/// error: silently dropping `%post` would produce a package that installs but does not work.
+fn read_scripts(config: &RevolveConfig, manifest_dir: &Path) -> Result<ScriptsContext> {
+  let Some(scripts) = &config.scripts else {
+    return Ok(ScriptsContext::default());
+  };
+
+  let read = |path: &Option<String>| -> Result<Option<String>> {
+    let Some(path) = path else {
+      return Ok(None);
+    };
+    let script_path = manifest_dir.join(path);
+    log::info!("Reading scriptlet from {}", script_path.display());
+    let script = fs::read_to_string(&script_path).map_err(|e| {
+      RevolveError::Config(format!(
+        "Failed to read scriptlet file at {}: {}",
+        script_path.display(),
+        e
+      ))
+    })?;
+    Ok(Some(script))
   };
 
+  Ok(ScriptsContext {
+    pre: read(&scripts.pre)?,
+    post: read(&scripts.post)?,
+    preun: read(&scripts.preun)?,
+    postun: read(&scripts.postun)?,
+    pretrans: read(&scripts.pretrans)?,
+    posttrans: read(&scripts.posttrans)?,
+  })
+}
+
+/// Renders the .spec template of `plan` to `<name>-<version>.spec` in `build_dir`.
+/// Returns the path of the written file and its contents.
+pub fn render_spec(plan: &PackagePlan, build_dir: &Path) -> Result<(PathBuf, String)> {
+  log::info!("Rendering .spec template...");
+  let package = plan.package;
+  let rendered = render_spec_content(plan)?;
+
+  let spec_filename = format!("{}-{}.spec", package.name, package.version);
+  let final_spec_path = build_dir.join(spec_filename);
+
+  fs::write(&final_spec_path, &rendered).with_context(|| {
+    format!(
+      "Failed to write rendered spec to {}",
+      final_spec_path.display()
+    )
+  })?;
+
+  log::info!(
+    "Successfully rendered spec file to {}",
+    final_spec_path.display()
+  );
+
+  Ok((final_spec_path, rendered))
+}
+
+/// Renders the .spec template of `plan` without writing it anywhere.
+pub fn render_spec_content(plan: &PackagePlan) -> Result<String> {
+  let template_path = plan.manifest_dir().join(&plan.config.spec_template);
+
+  let template_error = |source| RevolveError::Template {
+    path: template_path.clone(),
+    source,
+  };
   let mut tera = Tera::default();
   tera
     .add_template_file(&template_path, Some("spec"))
-    .with_context(|| {
-      format!(
-        "Failed to load spec template from {}",
-        template_path.display()
-      )
-    })?;
+    .map_err(template_error)?;
 
+  let context = template_context(plan)?;
+  tera.render("spec", &context).map_err(template_error)
+}
+
+/// The variables a .spec template is rendered with.
+pub(crate) fn template_context(plan: &PackagePlan) -> Result<tera::Context> {
+  let PackagePlan { ref config, package, .. } = *plan;
+  let changelog_content = read_changelog(config, plan.manifest_dir());
   let archive_root_dir = format!("{}-{}", package.name, package.version);
+  let archive_filename = archive_filename(config, package);
 
   let context = tera::Context::from_serialize(TemplateContext {
     pkg: PkgContext {
@@ -238,80 +1165,206 @@ fn render_spec(
     builder: BuilderContext {
       spec_template: &config.spec_template,
       archive_root_dir: &archive_root_dir,
+      archive_filename: &archive_filename,
+      rpm_arch: plan.rpm_arch,
       changelog: changelog_content.as_deref(),
       assets: config.assets.as_ref(),
       build_flags: config.build_flags.as_ref(),
-      created_dirs,
+      created_dirs: plan.created_dirs.as_deref(),
+      subpackages: &plan.subpackages,
+      requires: config.requires.as_deref().unwrap_or_default(),
+      provides: config.provides.as_deref().unwrap_or_default(),
+      conflicts: config.conflicts.as_deref().unwrap_or_default(),
+      obsoletes: config.obsoletes.as_deref().unwrap_or_default(),
+      build_requires: config.build_requires.as_deref().unwrap_or_default(),
+      auto_requires: &plan.auto_requires,
+      scripts: &plan.scripts,
+      systemd: &plan.systemd,
+      sysusers: &plan.sysusers,
     },
+  })
+  .map_err(|source| RevolveError::Template {
+    path: plan.manifest_dir().join(&config.spec_template),
+    source,
   })?;
+  Ok(context)
+}
 
-  let rendered = tera.render("spec", &context)?;
+/// Writes the source archive of `plan` to the package's `target/` directory: every asset source
+/// below `<name>-<version>/`, at its `archive_path`. Returns the path of the archive, which a
+/// dry run only checks for conflicting entries instead of writing.
+pub fn create_artifact_archive(plan: &PackagePlan, dry_run: bool) -> Result<PathBuf> {
+  log::info!("Creating artifact archive...");
 
-  let spec_filename = format!("{}-{}.spec", package.name, package.version);
-  let final_spec_path = build_dir.join(spec_filename);
+  let PackagePlan { ref config, package, target_dir, .. } = *plan;
+  let project_dir = plan.manifest_dir();
+  let archive_path = project_dir.join("target").join(archive_filename(config, package));
+
+  // Each source is stored once under its archive path; two different sources must not share one.
+  let subpackage_assets = plan.subpackages.iter().flat_map(|sub| sub.assets.iter());
+  let assets = config.assets.iter().flatten().chain(subpackage_assets);
+  let mut entries: BTreeMap<&str, &str> = BTreeMap::new();
+  for asset in assets {
+    let Some(entry_path) = &asset.archive_path else {
+      continue;
+    };
+    match entries.insert(entry_path, &asset.source) {
+      Some(existing_source) if existing_source != asset.source => {
+        return Err(RevolveError::Asset(format!(
+          "Archive path conflict: '{}' and '{}' would both be stored as '{}' in the source archive.",
+          existing_source, asset.source, entry_path
+        )));
+      }
+      _ => {}
+    }
+  }
 
-  fs::write(&final_spec_path, &rendered).with_context(|| {
-    format!(
-      "Failed to write rendered spec to {}",
-      final_spec_path.display()
-    )
-  })?;
+  if !dry_run {
+    let archive_root_dir = format!("{}-{}", package.name, package.version);
+    let mut files = Vec::with_capacity(entries.len());
+    for (entry_path, source) in entries {
+      let source_path = resolve_asset_source(source, project_dir, target_dir);
+      if !source_path.exists() {
+        return Err(RevolveError::MissingAssetSource(source_path));
+      }
+      // Sources keep their relative layout inside the archive.
+      files.push((source_path, Path::new(&archive_root_dir).join(entry_path)));
+    }
+    write_archive(&archive_path, config, &files, plan.source_date_epoch).map_err(|e| {
+      RevolveError::Archive {
+        path: archive_path.clone(),
+        source: e.into(),
+      }
+    })?;
+  }
+  Ok(archive_path)
+}
 
-  log::info!(
-    "Successfully rendered spec file to {}",
-    final_spec_path.display()
-  );
+/// Writes `files`, pairs of a source path and its path inside the archive, to a tar archive
+/// compressed as configured.
+fn write_archive(
+  archive_path: &Path,
+  config: &RevolveConfig,
+  files: &[(PathBuf, PathBuf)],
+  source_date_epoch: Option<u32>,
+) -> anyhow::Result<()> {
+  if let Some(parent) = archive_path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  let archive_file = fs::File::create(archive_path)?;
+  // None of the compressed formats record a timestamp or file name, so only the tar headers
+  // need care for reproducible builds.
+  let format = config.archive_compression.unwrap_or(CompressionFormat::Gzip);
+  let encoder = ArchiveEncoder::new(archive_file, format, config.archive_compression_level)?;
+  let mut builder = Builder::new(encoder);
+  for (source_path, dest_path) in files {
+    match source_date_epoch {
+      Some(epoch) => append_normalized(&mut builder, source_path, dest_path, epoch)?,
+      None => builder
+        .append_path_with_name(source_path, dest_path)
+        .with_context(|| format!("Failed to add {} to the archive", source_path.display()))?,
+    }
+  }
+  builder.into_inner()?.finish()?;
+  Ok(())
+}
 
-  Ok((final_spec_path, rendered))
+/// Appends a file with a header that only depends on its contents and permissions: root
+/// ownership, no user or group names and `epoch` as the modification time. The entries are
+/// already appended in sorted order.
+fn append_normalized<W: Write>(
+  builder: &mut Builder<W>,
+  source_path: &Path,
+  dest_path: &Path,
+  epoch: u32,
+) -> anyhow::Result<()> {
+  let file = fs::File::open(source_path)
+    .with_context(|| format!("Failed to open {}", source_path.display()))?;
+  let mut header = Header::new_gnu();
+  header.set_metadata_in_mode(&file.metadata()?, HeaderMode::Deterministic);
+  header.set_mtime(epoch.into());
+  builder
+    .append_data(&mut header, dest_path, file)
+    .with_context(|| format!("Failed to add {} to the archive", source_path.display()))
 }
 
-fn create_artifact_archive(
-  config: &RevolveConfig,
-  package: &CargoPackage,
+/// Resolves an asset `source` to a path on disk.
+///
+/// Sources starting with `target/` are build artifacts and are resolved against the real Cargo
+/// target directory, which may live outside the project in a workspace.
+pub(crate) fn resolve_asset_source(source: &str, project_dir: &Path, target_dir: &Path) -> PathBuf {
+  match source.strip_prefix("target/") {
+    Some(artifact) => target_dir.join(artifact),
+    None => project_dir.join(source),
+  }
+}
+
+/// The inverse of [`resolve_asset_source`]: the source, relative to the project, of a file found
+/// below the directory of a directory or glob asset `source`.
+fn asset_source_path(
+  path: &Path,
+  source: &str,
+  project_dir: &Path,
   target_dir: &Path,
-  dry_run: bool,
 ) -> Result<PathBuf> {
-  log::info!("Creating artifact archive...");
-
-  let project_dir = package.manifest_path.parent().unwrap().as_std_path();
-  let archive_filename = format!("{}-{}.tar.gz", package.name, package.version);
-  let archive_path = project_dir.join("target").join(&archive_filename);
-
-  if !dry_run {
-    let gz_file = fs::File::create(&archive_path)?;
-    let encoder = GzEncoder::new(gz_file, Compression::default());
-    let mut builder = Builder::new(encoder);
-    let archive_root_dir = format!("{}-{}", package.name, package.version);
+  if source.starts_with("target/") {
+    let artifact = path
+      .strip_prefix(target_dir)
+      .context("Walked outside of the target directory")?;
+    return Ok(Path::new("target").join(artifact));
+  }
+  Ok(path
+    .strip_prefix(project_dir)
+    .context("Walked outside of the project directory")?
+    .to_path_buf())
+}
 
-    if let Some(assets) = &config.assets {
-      for asset in assets {
-        let source_path = if asset.source.starts_with("target/") {
-          // This is a build artifact, resolve it from the true target directory.
-          // We strip "target/" from the start of the source path.
-          target_dir.join(asset.source.strip_prefix("target/").unwrap())
-        } else {
-          // This is a project file, resolve it from the project's own directory.
-          project_dir.join(&asset.source)
-        };
-
-        if !source_path.exists() {
-          bail!(
-            "Asset source file not found: {}. Please run 'cargo build' first or ensure the path is correct.",
-            source_path.display()
-          );
-        }
-        // The destination inside the archive is just the filename.
-        let dest_path = Path::new(&archive_root_dir).join(source_path.file_name().unwrap());
-        builder.append_path_with_name(&source_path, dest_path)?;
+/// Rewrites `target/<profile>/...` asset sources to `target/<triple>/<profile>/...`, which is
+/// where Cargo places artifacts when building with `--target`.
+pub(crate) fn retarget_assets(
+  assets: &[Asset],
+  triple: &str,
+  build_flags: Option<&[String]>,
+) -> Vec<Asset> {
+  let mut profile_dirs = vec!["release".to_string(), "debug".to_string()];
+  if let Some(flags) = build_flags {
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+      if let Some(profile) = flag.strip_prefix("--profile=") {
+        profile_dirs.push(profile.to_string());
+      } else if flag == "--profile" && let Some(profile) = flags.next() {
+        profile_dirs.push(profile.to_string());
       }
     }
-    builder.into_inner()?.finish()?;
   }
-  Ok(archive_path)
+
+  assets
+    .iter()
+    .map(|asset| {
+      let mut asset = asset.clone();
+      if let Some(rest) = asset.source.strip_prefix("target/") {
+        let first_component = rest.split('/').next().unwrap_or_default();
+        if profile_dirs.iter().any(|p| p == first_component) {
+          log::debug!("Retargeting asset source '{}' to triple {}", asset.source, triple);
+          asset.source = format!("target/{}/{}", triple, rest);
+        }
+      }
+      asset
+    })
+    .collect()
+}
+
+/// Renders a command as a shell-like string for dry-run output.
+fn format_command(cmd: &Command) -> String {
+  std::iter::once(cmd.get_program())
+    .chain(cmd.get_args())
+    .map(|part| part.to_string_lossy().into_owned())
+    .collect::<Vec<_>>()
+    .join(" ")
 }
 
 /// A helper to spawn a command, stream its output, and wait for it to complete.
-fn stream_command(cmd: &mut Command) -> Result<ExitStatus> {
+fn stream_command(cmd: &mut Command, message_format: MessageFormat) -> Result<ExitStatus> {
   let mut child = cmd
     .stdout(std::process::Stdio::piped())
     .stderr(std::process::Stdio::piped())
@@ -321,10 +1374,10 @@ fn stream_command(cmd: &mut Command) -> Result<ExitStatus> {
   let stdout = child.stdout.take().unwrap();
   let stderr = child.stderr.take().unwrap();
 
-  let stdout_thread = thread::spawn(|| {
+  let stdout_thread = thread::spawn(move || {
     let reader = BufReader::new(stdout);
     for line in reader.lines() {
-      println!("{}", line.unwrap());
+      status!(message_format, "{}", line.unwrap());
     }
   });
 
@@ -345,24 +1398,30 @@ fn stream_command(cmd: &mut Command) -> Result<ExitStatus> {
   Ok(status)
 }
 
+/// Compiles the package with `cargo build` or the custom `build_command`. The commands that were
+/// run are added to `commands`.
 fn execute_build_process(
   config: &RevolveConfig,
   package: &CargoPackage,
   target_dir: &Path,
-  dry_run: bool,
+  target_triple: Option<&str>,
+  rpm_arch: &str,
+  options: &BuildOptions,
+  commands: &mut Vec<CommandReport>,
 ) -> Result<()> {
+  let BuildOptions { dry_run, message_format, .. } = *options;
   let project_dir = package.manifest_path.parent().unwrap().as_std_path();
 
   // If a custom build command is specified, use it.
   if let Some(build_command) = &config.build_command {
     if dry_run {
-      println!("\n--- Dry Run: Build Step ---");
-      println!("The following build command(s) would be executed:");
+      status!(message_format, "\n--- Dry Run: Build Step ---");
+      status!(message_format, "The following build command(s) would be executed:");
       match build_command {
-        BuildCommand::Single(cmd) => println!("  - {}", cmd),
+        BuildCommand::Single(cmd) => status!(message_format, "  - {}", cmd),
         BuildCommand::Sequence(cmds) => {
           for cmd in cmds {
-            println!("  - {}", cmd);
+            status!(message_format, "  - {}", cmd);
           }
         }
       }
@@ -380,21 +1439,27 @@ fn execute_build_process(
     env_vars.insert("REVOLVE_PACKAGE_NAME", package.name.to_string());
     // Now we use a reference to the `package_version_str` which has a valid lifetime.
     env_vars.insert("REVOLVE_PACKAGE_VERSION", package_version_str);
+    env_vars.insert("REVOLVE_RPM_ARCH", rpm_arch.to_string());
+    if let Some(triple) = target_triple {
+      env_vars.insert("REVOLVE_TARGET", triple.to_string());
+    }
 
     // --- START: FIX 2 ---
     // We need to work with references to the strings to avoid cloning and ownership issues.
-    let commands: Vec<&String> = match build_command {
+    let command_strs: Vec<&String> = match build_command {
       BuildCommand::Single(cmd) => vec![cmd],
       BuildCommand::Sequence(cmds) => cmds.iter().collect(),
     };
     // --- END: FIX 2 ---
 
-    for command_str in commands {
+    for command_str in command_strs {
       log::info!("Running: `{}`", command_str);
 
       // Use shlex to safely parse the command string.
       let parts = shlex::split(command_str)
-        .ok_or_else(|| anyhow::anyhow!("Failed to parse command string: {}", command_str))?;
+        .ok_or_else(|| {
+          RevolveError::Config(format!("Failed to parse command string: {}", command_str))
+        })?;
       if parts.is_empty() {
         continue; // Skip empty commands
       }
@@ -405,19 +1470,19 @@ fn execute_build_process(
         .current_dir(project_dir)
         .envs(&env_vars);
 
-      let status = stream_command(&mut cmd)?;
+      let started = Instant::now();
+      let status = stream_command(&mut cmd, message_format)?;
 
       if !status.success() {
-        bail!(
-          "Custom build command failed with exit code {}: `{}`",
+        return Err(RevolveError::BuildCommand {
+          command: command_str.clone(),
           status,
-          command_str
-        );
+        });
       }
+      commands.push(CommandReport::new(command_str.clone(), started.elapsed()));
     }
   } else {
     // Fallback to the default `cargo build` behavior.
-    log::info!("Compiling package with 'cargo build'...");
     let mut cmd = Command::new("cargo");
     cmd
       .arg("build")
@@ -425,6 +1490,10 @@ fn execute_build_process(
       .arg("--target-dir")
       .arg(target_dir);
 
+    if let Some(triple) = target_triple {
+      cmd.arg("--target").arg(triple);
+    }
+
     // `build_flags` are only used in the default case.
     if let Some(flags) = &config.build_flags {
       cmd.args(flags);
@@ -435,22 +1504,39 @@ fn execute_build_process(
       cmd.arg("--release");
     }
 
-    let status = stream_command(&mut cmd)?;
+    if dry_run {
+      status!(message_format, "\n--- Dry Run: Build Step ---");
+      status!(message_format, "The following build command would be executed:");
+      status!(message_format, "  - {}", format_command(&cmd));
+      return Ok(());
+    }
+
+    log::info!("Compiling package with 'cargo build'...");
+
+    let started = Instant::now();
+    let status = stream_command(&mut cmd, message_format)?;
 
     if !status.success() {
-      bail!("'cargo build' failed with exit code: {}", status);
+      return Err(RevolveError::BuildCommand {
+        command: format_command(&cmd),
+        status,
+      });
     }
+    commands.push(CommandReport::new(format_command(&cmd), started.elapsed()));
   }
 
   Ok(())
 }
 
 fn execute_rpmbuild(
+  plan: &PackagePlan,
   archive_path: Option<&Path>,
   spec_path: &Path, // This is the path to the spec file in our `target/revolve/build` dir
   rpmbuild_dir: &Path,
-  project_root: &Path,
-) -> Result<()> {
+  rpm_target: Option<&str>,
+  defines: &[String],
+) -> Result<CommandReport> {
+  let project_root = plan.manifest_dir();
   log::info!("Executing 'rpmbuild' using compatible method...");
 
   let sources_dir = rpmbuild_dir.join("SOURCES");
@@ -487,15 +1573,28 @@ fn execute_rpmbuild(
     cmd.arg("-bb").arg(&final_spec_path).arg(sourcedir_arg);
   }
 
+  if let Some(arch) = rpm_target {
+    log::debug!("Building for RPM architecture {}", arch);
+    cmd.arg("--target").arg(arch);
+  }
+
+  if let Some(epoch) = plan.source_date_epoch {
+    cmd.env("SOURCE_DATE_EPOCH", epoch.to_string());
+  }
+  for define in defines {
+    cmd.arg(format!("--define={}", define));
+  }
+
   // Use the new `stream_command` helper here for consistency.
-  let status = stream_command(&mut cmd)?;
+  let started = Instant::now();
+  let status = stream_command(&mut cmd, plan.message_format)?;
 
   if !status.success() {
-    bail!("'rpmbuild' failed with exit code: {}", status);
+    return Err(RevolveError::Rpmbuild(status));
   }
 
   log::info!("'rpmbuild' executed successfully.");
-  Ok(())
+  Ok(CommandReport::new(format_command(&cmd), started.elapsed()))
 }
 
 // collect_artifacts now returns a list of found RPMs
@@ -503,6 +1602,7 @@ fn collect_artifacts(
   rpmbuild_dir: &Path,
   output_dir: &Option<String>,
   project_root: &Path,
+  message_format: MessageFormat,
 ) -> Result<Vec<PathBuf>> {
   log::info!("Collecting build artifacts...");
 
@@ -527,7 +1627,7 @@ fn collect_artifacts(
     // Walk the directory to find any .rpm files
     for entry in walkdir::WalkDir::new(rpms_dir) {
       let entry = entry.context("Failed to read directory entry")?;
-      if entry.path().extension().map_or(false, |e| e == "rpm") {
+      if entry.path().extension().is_some_and(|e| e == "rpm") {
         let source_path = entry.path();
         log::info!("Found RPM artifact: {}", source_path.display());
 
@@ -555,45 +1655,82 @@ fn collect_artifacts(
   if found_rpms.is_empty() {
     log::warn!("No RPM files were found in the output directory.");
   } else {
-    println!("Successfully built {} RPM package(s).", found_rpms.len());
+    status!(message_format, "Successfully built {} RPM package(s).", found_rpms.len());
   }
 
   Ok(found_rpms)
 }
 
-fn verify_package(
-  rpm_path: &Path,
-  cargo_package: &CargoPackage,
-  config: &RevolveConfig,
-) -> Result<()> {
-  println!("Verifying {}...", rpm_path.display());
+/// What a built RPM is expected to contain, checked by `verify_package`.
+struct ExpectedPackage<'a> {
+  name: &'a str,
+  license: Option<&'a str>,
+  summary: Option<&'a str>,
+  assets: Option<&'a [Asset]>,
+  requires: &'a [Dependency],
+  provides: &'a [Dependency],
+  conflicts: &'a [Dependency],
+  obsoletes: &'a [Dependency],
+  scripts: Option<&'a ScriptsContext>,
+}
+
+/// Checks one built RPM against the main package or the subpackage of `plan` it was built for:
+/// its name, version, files, permissions, ownership, file kinds, contents, dependencies and
+/// scriptlets.
+pub fn verify_package(plan: &PackagePlan, rpm_path: &Path) -> Result<()> {
+  let rpm_package = RpmPackage::open(rpm_path).map_err(|e| {
+    RevolveError::Verification(format!(
+      "Verification failed: cannot open and parse {}: {}",
+      rpm_path.display(),
+      e
+    ))
+  })?;
+  let name = rpm_package.metadata.get_name()?;
+  let expected_packages = expected_packages(plan);
+  let expected = expected_packages.iter().find(|expected| expected.name == name).ok_or_else(|| {
+    RevolveError::Verification(format!(
+      "Verification failed: '{}' is not a package built from {}.",
+      name, plan.package.name
+    ))
+  })?;
+  check_package(plan, rpm_path, expected)
+}
+
+fn check_package(plan: &PackagePlan, rpm_path: &Path, expected: &ExpectedPackage) -> Result<()> {
+  status!(plan.message_format, "Verifying {}...", rpm_path.display());
+  let expected_version = plan.package.version.to_string();
 
-  let rpm_package = RpmPackage::open(rpm_path)
-    .context("Failed to open and parse the generated RPM for verification")?;
+  let rpm_package = RpmPackage::open(rpm_path).map_err(|e| {
+    RevolveError::Verification(format!(
+      "Verification failed: cannot open and parse {}: {}",
+      rpm_path.display(),
+      e
+    ))
+  })?;
   let metadata = &rpm_package.metadata;
   let mut issues_found = 0;
 
   // 1. Verify package metadata
   log::debug!("Verifying package metadata (Name, Version, etc.)...");
-  if metadata.get_name()? != cargo_package.name.as_str() {
+  if metadata.get_name()? != expected.name {
     log::error!(
       "Verification failed: Name mismatch. Expected '{}', found '{}'",
-      cargo_package.name,
+      expected.name,
       metadata.get_name()?
     );
     issues_found += 1;
   }
-  if metadata.get_version()? != cargo_package.version.to_string() {
+  if metadata.get_version()? != expected_version {
     log::error!(
       "Verification failed: Version mismatch. Expected '{}', found '{}'",
-      cargo_package.version,
+      expected_version,
       metadata.get_version()?
     );
     issues_found += 1;
   }
 
   // Verify license if configured
-  if let Some(expected_license) = &config.verify_license {
+  if let Some(expected_license) = expected.license {
     let actual_license = metadata.get_license().unwrap_or("N/A");
     if actual_license != expected_license {
       log::error!(
@@ -606,7 +1743,7 @@ fn verify_package(
   }
 
   // Verify summary if configured
-  if let Some(expected_summary) = &config.verify_summary {
+  if let Some(expected_summary) = expected.summary {
     let actual_summary = metadata.get_summary().unwrap_or("N/A");
     if actual_summary != expected_summary {
       log::error!(
@@ -619,7 +1756,7 @@ fn verify_package(
   }
 
   // 2. Verify file manifest and permissions
-  if let Some(expected_assets) = &config.assets {
+  if let Some(expected_assets) = expected.assets {
     log::debug!("Verifying package file manifest and permissions...");
     // Fetch all file metadata at once and create a HashMap for efficient lookups.
     let actual_files_with_meta: std::collections::HashMap<_, _> = metadata
@@ -628,6 +1765,8 @@ fn verify_package(
       .map(|entry| (entry.path.clone(), entry))
       .collect();
 
+    // Binaries whose digest differs from their source, with the build ID of the source.
+    let mut changed_binaries = Vec::new();
     for asset in expected_assets {
       let expected_path = PathBuf::from(&asset.dest);
       match actual_files_with_meta.get(&expected_path) {
@@ -640,7 +1779,21 @@ fn verify_package(
         }
         Some(file_entry) => {
           // Check permissions if specified in config
-          if let Some(expected_mode_str) = &asset.mode {
+          // Symlinks are checked against their target instead of permissions.
+          if let Some(expected_target) = &asset.symlink_target {
+            if !matches!(file_entry.mode, rpm::FileMode::SymbolicLink { .. }) {
+              log::error!("Verification failed: '{}' is not a symlink in the package", asset.dest);
+              issues_found += 1;
+            } else if &file_entry.linkto != expected_target {
+              log::error!(
+                "Verification failed: Symlink target mismatch for '{}'. Expected '{}', found '{}'",
+                asset.dest,
+                expected_target,
+                file_entry.linkto
+              );
+              issues_found += 1;
+            }
+          } else if let Some(expected_mode_str) = &asset.mode {
             let expected_mode = u16::from_str_radix(expected_mode_str, 8).with_context(|| {
               format!(
                 "Invalid octal mode '{}' for asset {}",
@@ -661,26 +1814,289 @@ fn verify_package(
               issues_found += 1;
             }
           }
+
+          // Check ownership if specified in config
+          let ownership = [
+            ("user", asset.user.as_deref(), &file_entry.ownership.user),
+            ("group", asset.group.as_deref(), &file_entry.ownership.group),
+          ];
+          for (field, expected_owner, actual_owner) in ownership {
+            if let Some(expected_owner) = expected_owner
+              && expected_owner != actual_owner
+            {
+              log::error!(
+                "Verification failed: Owning {} mismatch for file '{}'. Expected '{}', found '{}'",
+                field,
+                asset.dest,
+                expected_owner,
+                actual_owner
+              );
+              issues_found += 1;
+            }
+          }
+
+          // Check the %files attributes of assets that declare a kind. Plain files are not
+          // checked, as rpmbuild marks some of them (e.g. man pages) as %doc on its own.
+          if let Some(kind) = asset.kind {
+            let expected_flags = native::file_flags(kind);
+            let actual_flags = file_entry.flags & native::file_flags_mask();
+            if actual_flags != expected_flags {
+              log::error!(
+                "Verification failed: File flags mismatch for '{}'. Expected {:?}, found {:?}",
+                asset.dest,
+                expected_flags,
+                actual_flags
+              );
+              issues_found += 1;
+            }
+            if kind == AssetKind::Dir && !matches!(file_entry.mode, rpm::FileMode::Dir { .. }) {
+              log::error!("Verification failed: '{}' is not a directory in the package", asset.dest);
+              issues_found += 1;
+            }
+          }
+
+          // Check the contents, so a file left over from an old build is not shipped unnoticed.
+          if let Some(source_path) = changed_source(asset, file_entry, plan)? {
+            let source = fs::read(&source_path)
+              .with_context(|| format!("Failed to read {}", source_path.display()))?;
+            match elf::build_id(&source) {
+              // rpmbuild strips binaries, which changes their digest but not their build ID.
+              Some(build_id) if plan.backend == Backend::Rpmbuild => {
+                changed_binaries.push((expected_path.clone(), build_id))
+              }
+              // Without a build ID, a stripped binary cannot be told from a stale one.
+              None if plan.backend == Backend::Rpmbuild && elf::is_elf(&source) => {
+                log::error!(
+                  "Verification failed: Contents mismatch for '{}'. {} has no GNU build ID to \
+                   tell a stripped copy from a stale one",
+                  asset.dest,
+                  source_path.display()
+                );
+                issues_found += 1;
+              }
+              _ => {
+                log::error!(
+                  "Verification failed: Contents mismatch for '{}'. The package does not hold \
+                   the current {}",
+                  asset.dest,
+                  source_path.display()
+                );
+                issues_found += 1;
+              }
+            }
+          }
+        }
+      }
+    }
+    if !changed_binaries.is_empty() {
+      issues_found += check_build_ids(&rpm_package, &changed_binaries)?;
+    }
+  }
+
+  // 3. Verify the declared dependency headers
+  log::debug!("Verifying package dependencies...");
+  let declared_dependencies = [
+    ("Requires", expected.requires, metadata.get_requires()?),
+    ("Provides", expected.provides, metadata.get_provides()?),
+    ("Conflicts", expected.conflicts, metadata.get_conflicts()?),
+    ("Obsoletes", expected.obsoletes, metadata.get_obsoletes()?),
+  ];
+  for (tag, declared, actual) in &declared_dependencies {
+    for dependency in *declared {
+      if !actual.iter().any(|found| native::dependency_matches(dependency, found)) {
+        log::error!(
+          "Verification failed: Expected '{}: {}' not found in package",
+          tag,
+          dependency
+        );
+        issues_found += 1;
+      }
+    }
+  }
+
+  // 4. Verify the scriptlets carry the contents of the configured files
+  if let Some(scripts) = expected.scripts {
+    log::debug!("Verifying package scriptlets...");
+    for (name, expected_script) in scripts.iter() {
+      let actual = match name {
+        "pre" => metadata.get_pre_install_script(),
+        "post" => metadata.get_post_install_script(),
+        "preun" => metadata.get_pre_uninstall_script(),
+        "postun" => metadata.get_post_uninstall_script(),
+        "pretrans" => metadata.get_pre_trans_script(),
+        _ => metadata.get_post_trans_script(),
+      };
+      match actual {
+        // The scriptlet may also carry generated snippets (e.g. for systemd units), and
+        // templates and rpmbuild may add or drop surrounding blank lines.
+        Ok(scriptlet) if scriptlet.script.contains(expected_script.trim()) => {}
+        Ok(_) => {
+          log::error!(
+            "Verification failed: The %{} scriptlet does not match its configured file",
+            name
+          );
+          issues_found += 1;
+        }
+        Err(_) => {
+          log::error!("Verification failed: Expected %{} scriptlet not found in package", name);
+          issues_found += 1;
         }
       }
     }
   }
 
   if issues_found > 0 {
-    bail!("{} verification issue(s) found.", issues_found);
+    return Err(RevolveError::Verification(format!(
+      "{} verification issue(s) found.",
+      issues_found
+    )));
   } else {
-    println!("Verification successful. Package contents match configuration.");
+    status!(plan.message_format, "Verification successful. Package contents match configuration.");
   }
 
   Ok(())
 }
 
-/// Expands assets with trailing slashes into a list of file-only assets.
-/// This function walks the source directory and creates an asset for each file found.
-/// It also handles deduplication and returns a list of all unique parent directories.
-fn expand_assets(
+/// Compares the SHA-256 digest the RPM records for an asset with a hash of its source, resolved
+/// the same way as for the source archive. Returns the source if they differ. Directories,
+/// symlinks, ghost files and digests of other algorithms are not checked.
+///
+/// Besides stripping binaries, `rpmbuild` may rewrite other files while building: the
+/// `brp-mangle-shebangs` policy changes `#!/usr/bin/env` interpreter lines, libtool `.la`
+/// files can be edited or removed and Python bytecode is recompiled. Such files differ from
+/// their sources, and verifying them fails.
+fn changed_source(
+  asset: &Asset,
+  file_entry: &rpm::FileEntry,
+  plan: &PackagePlan,
+) -> Result<Option<PathBuf>> {
+  if !asset.has_source()
+    || asset.kind == Some(AssetKind::Ghost)
+    || !matches!(file_entry.mode, rpm::FileMode::Regular { .. })
+  {
+    return Ok(None);
+  }
+  let Some(digest) =
+    file_entry.digest.as_ref().filter(|digest| digest.algo == rpm::DigestAlgorithm::Sha2_256)
+  else {
+    return Ok(None);
+  };
+
+  let source_path = resolve_asset_source(&asset.source, plan.manifest_dir(), plan.target_dir);
+  let mut file = fs::File::open(&source_path).with_context(|| {
+    format!("Failed to open {} to verify '{}'", source_path.display(), asset.dest)
+  })?;
+  let mut hasher = Sha256::new();
+  io::copy(&mut file, &mut hasher)
+    .with_context(|| format!("Failed to hash {}", source_path.display()))?;
+  let source_digest = format!("{:x}", hasher.finalize());
+  Ok((source_digest != digest.digest).then_some(source_path))
+}
+
+/// Checks that the packaged copies of `binaries` carry the build ID of their source, i.e. that
+/// they were only stripped. Returns the number of binaries that are from another build or have
+/// no build ID to compare.
+fn check_build_ids(rpm_package: &RpmPackage, binaries: &[(PathBuf, Vec<u8>)]) -> Result<usize> {
+  log::debug!("Comparing the build IDs of {} stripped binaries...", binaries.len());
+  let mut issues_found = 0;
+  for file in rpm_package.files()? {
+    let file = file?;
+    let Some((path, build_id)) = binaries.iter().find(|(path, _)| *path == file.metadata.path)
+    else {
+      continue;
+    };
+    match elf::build_id(&file.content) {
+      Some(packaged) if packaged == *build_id => {}
+      Some(_) => {
+        log::error!(
+          "Verification failed: Contents mismatch for '{}'. The package holds a binary from \
+           another build",
+          path.display()
+        );
+        issues_found += 1;
+      }
+      None => {
+        log::error!(
+          "Verification failed: Contents mismatch for '{}'. The packaged binary has no GNU \
+           build ID to tell a stripped copy from a stale one",
+          path.display()
+        );
+        issues_found += 1;
+      }
+    }
+  }
+  Ok(issues_found)
+}
+
+/// Expands the assets of every configured subpackage.
+/// A destination may only be provided by one package, so collisions with the main package's
+/// (already expanded) assets or another subpackage are rejected.
+fn expand_subpackages(
+  config: &RevolveConfig,
+  package: &CargoPackage,
+  project_root: &Path,
+  target_dir: &Path,
+  target_triple: Option<&str>,
+  main_assets: &[Asset],
+) -> Result<Vec<SubpackageContext>> {
+  let Some(subpackages) = &config.subpackages else {
+    return Ok(Vec::new());
+  };
+
+  let mut owners: HashMap<String, String> = main_assets
+    .iter()
+    .map(|asset| (asset.dest.clone(), package.name.to_string()))
+    .collect();
+  let mut expanded = Vec::with_capacity(subpackages.len());
+
+  for (name, subpackage) in subpackages {
+    let full_name = format!("{}-{}", package.name, name);
+    log::info!("Expanding assets for subpackage {}...", full_name);
+
+    let initial_assets = subpackage.assets.clone().unwrap_or_default();
+    let initial_assets = match target_triple {
+      Some(triple) => retarget_assets(&initial_assets, triple, config.build_flags.as_deref()),
+      None => initial_assets,
+    };
+    let (assets, created_dirs) = expand_assets(&initial_assets, project_root, target_dir)?;
+
+    for asset in &assets {
+      if let Some(owner) = owners.insert(asset.dest.clone(), full_name.clone()) {
+        return Err(RevolveError::DuplicateDestination {
+          dest: asset.dest.clone(),
+          first: format!("package '{}'", owner),
+          second: format!("package '{}'", full_name),
+        });
+      }
+    }
+
+    expanded.push(SubpackageContext {
+      name: name.clone(),
+      full_name,
+      summary: subpackage.summary.clone(),
+      description: subpackage.description.clone(),
+      requires: subpackage.requires.clone().unwrap_or_default(),
+      auto_requires: Vec::new(),
+      assets,
+      created_dirs,
+    });
+  }
+
+  Ok(expanded)
+}
+
+/// Expands directory (`source` ending in `/`) and glob assets into one asset per file, as
+/// [`PackagePlan::new`] does.
+///
+/// Sources are relative to `project_root`; sources below `target/`, including the directories
+/// and globs that are walked, are looked up in the Cargo `target_dir` instead. Symlink, `dir`
+/// and `ghost` assets are kept as they are. Returns the expanded assets and the directories
+/// they create. A missing source or two assets with the same destination are asset errors
+/// (exit code 5).
+pub fn expand_assets(
   initial_assets: &[Asset],
   project_root: &Path,
+  target_dir: &Path,
 ) -> Result<(Vec<Asset>, Vec<String>)> {
 
   let mut final_assets = Vec::new();
@@ -688,14 +2104,47 @@ fn expand_assets(
   let mut unique_dirs: HashSet<PathBuf> = HashSet::new();
 
   for asset in initial_assets {
-    // A trailing slash is the convention for a directory.
-    if asset.source.ends_with('/') {
-      let source_dir_path = project_root.join(&asset.source);
+    if let Some(target) = &asset.symlink_target {
+      validate_symlink_asset(asset, target)?;
+    } else if asset.source.is_empty()
+      && !matches!(asset.kind, Some(AssetKind::Ghost | AssetKind::Dir))
+    {
+      return Err(RevolveError::Asset(format!(
+        "Asset '{}' has no `source`; only `ghost`, `dir` and symlink assets may omit it.",
+        asset.dest
+      )));
+    }
+
+    let source_glob = if asset.source.contains(GLOB_CHARS) {
+      let glob = GlobBuilder::new(&asset.source)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| {
+          RevolveError::Asset(format!("Invalid glob pattern '{}' in asset source: {}", asset.source, e))
+        })?;
+      Some(glob.compile_matcher())
+    } else {
+      None
+    };
+
+    // A trailing slash is the convention for a directory. `dir` assets are never expanded.
+    if source_glob.is_some() || (asset.source.ends_with('/') && asset.kind != Some(AssetKind::Dir)) {
+      // A glob is walked from the directory before its first wildcard, e.g. `config/` for
+      // `config/**/*.toml`, and destinations are relative to that directory.
+      let source_dir = match &source_glob {
+        Some(_) => glob_base(&asset.source),
+        None => asset.source.clone(),
+      };
+      let source_dir_path = resolve_asset_source(&source_dir, project_root, target_dir);
       if !source_dir_path.is_dir() {
-        bail!(
-          "Asset source '{}' is marked as a directory (ends with '/') but is not a directory on disk.",
-          asset.source
-        );
+        if source_glob.is_some() {
+          return Err(RevolveError::Asset(format!(
+            "Asset source '{}' is a glob, but its base directory {} does not exist.",
+            asset.source,
+            source_dir_path.display()
+          )));
+        }
+        return Err(RevolveError::NotADirectory(asset.source.clone()));
       }
 
       log::debug!("Expanding directory asset: {}", asset.source);
@@ -705,17 +2154,51 @@ fn expand_assets(
         unique_dirs.insert(top_level_dest_dir.clone());
       }
 
-      // Walk the directory recursively.
-      for entry in WalkDir::new(&source_dir_path) { // <-- Don't use min_depth(1) so we can create empty dirs
-        let entry = entry?;
+      let assets_before = final_assets.len();
+      for entry in walk_source_dir(asset, &source_dir_path)? { // <-- The root is included so we can create empty dirs
+        let entry =
+          entry.with_context(|| format!("Failed to read asset source '{}'", asset.source))?;
         let entry_path = entry.path();
 
-        // Calculate the file's path relative to the source directory.
-        let relative_path = entry_path.strip_prefix(&source_dir_path)?;
+        // Calculate the file's path relative to the source directory and to the project.
+        let relative_path = entry_path
+          .strip_prefix(&source_dir_path)
+          .context("Walked outside of the asset source directory")?;
+        let project_path = asset_source_path(entry_path, &asset.source, project_root, target_dir)?;
+        let project_path = project_path.as_path();
 
         // Create the final destination path for this entry.
         let dest_path = PathBuf::from(&asset.dest).join(relative_path);
 
+        if let Some(glob) = &source_glob {
+          // Only matching files are packaged, so only their parents become directories.
+          if entry_path.is_dir() || !glob.is_match(project_path) {
+            continue;
+          }
+          if asset.mkdir {
+            let dest_root = Path::new(&asset.dest);
+            let parents = dest_path.ancestors().skip(1);
+            unique_dirs.extend(
+              parents.take_while(|dir| *dir != dest_root).map(Path::to_path_buf),
+            );
+          }
+        }
+
+        if asset.preserve_symlinks && entry.path_is_symlink() {
+          let target = fs::read_link(entry_path)
+            .with_context(|| format!("Failed to read symlink {}", entry_path.display()))?;
+          let symlink = Asset {
+            source: String::new(),
+            dest: dest_path.to_string_lossy().into_owned(),
+            mode: None,
+            symlink_target: Some(target.to_string_lossy().into_owned()),
+            ..asset.clone()
+          };
+          insert_destination(&mut destination_map, &dest_path, &symlink)?;
+          final_assets.push(symlink);
+          continue;
+        }
+
         // If it's a directory, just add it to our set and continue.
         if entry_path.is_dir() {
           if asset.mkdir {
@@ -725,50 +2208,54 @@ fn expand_assets(
         }
 
         // Check for duplicates.
-        if let Some(existing_source) = destination_map.get(&dest_path) {
-          bail!(
-            "Duplicate asset destination found: '{}'.\n  - Provided by source: '{}'\n  - Also provided by source: '{}'",
-            dest_path.display(),
-            existing_source,
-            asset.source,
-          );
-        }
-        destination_map.insert(dest_path.clone(), asset.source.clone());
+        insert_destination(&mut destination_map, &dest_path, asset)?;
 
         // Create the new, expanded asset for this file.
+        // Everything else, including the `kind`, is inherited from the directory asset.
         final_assets.push(Asset {
-          source: entry_path.strip_prefix(project_root)?.to_string_lossy().into_owned(),
+          source: project_path.to_string_lossy().into_owned(),
           dest: dest_path.to_string_lossy().into_owned(),
-          mode: asset.mode.clone(),
-          mkdir: asset.mkdir, 
+          ..asset.clone()
         });
       }
+
+      if source_glob.is_some() && final_assets.len() == assets_before {
+        return Err(RevolveError::Asset(format!(
+          "Asset source '{}' did not match any files.",
+          asset.source
+        )));
+      }
     } else {
+      if asset.exclude.is_some() || asset.gitignore {
+        return Err(RevolveError::Asset(format!(
+          "Asset '{}' sets `exclude` or `gitignore`, which only apply to directory and glob sources.",
+          asset.dest
+        )));
+      }
       // This is a single file asset.
       let dest_path = PathBuf::from(&asset.dest);
       
       // Only add its parent directory if the mkdir flag is true.
-      if asset.mkdir {
-        if let Some(parent) = dest_path.parent() {
-          if parent.components().next().is_some() {
-             unique_dirs.insert(parent.to_path_buf());
-          }
-        }
+      if asset.mkdir
+        && let Some(parent) = dest_path.parent()
+        && parent.components().next().is_some()
+      {
+        unique_dirs.insert(parent.to_path_buf());
       }
 
-      if let Some(existing_source) = destination_map.get(&dest_path) {
-         bail!(
-            "Duplicate asset destination found: '{}'.\n  - Provided by source: '{}'\n  - Also provided by source: '{}'",
-            dest_path.display(),
-            existing_source,
-            asset.source,
-          );
-      }
-      destination_map.insert(dest_path, asset.source.clone());
+      insert_destination(&mut destination_map, &dest_path, asset)?;
       final_assets.push(asset.clone());
     }
   }
 
+  for asset in &mut final_assets {
+    asset.archive_path = asset.has_source().then(|| archive_path_for(&asset.source));
+    asset.source_path = asset.has_source().then(|| {
+      let path = resolve_asset_source(&asset.source, project_root, target_dir);
+      path.to_string_lossy().into_owned()
+    });
+  }
+
   // Convert the set of PathBufs to a sorted Vec of Strings.
   let mut sorted_dirs: Vec<String> = unique_dirs
     .into_iter()
@@ -779,3 +2266,114 @@ fn expand_assets(
   Ok((final_assets, sorted_dirs))
 }
 
+/// The path an asset source is stored at inside the artifact archive: the source itself, minus
+/// anything that would escape the archive root (`/`, `.` and `..` components).
+fn archive_path_for(source: &str) -> String {
+  let path: PathBuf = Path::new(source)
+    .components()
+    .filter_map(|component| match component {
+      Component::Normal(part) => Some(part),
+      _ => None,
+    })
+    .collect();
+  path.to_string_lossy().into_owned()
+}
+
+/// Characters that make an asset `source` a glob pattern rather than a path.
+pub(crate) const GLOB_CHARS: &[char] = &['*', '?', '[', '{'];
+
+/// The leading components of a glob pattern that contain no wildcards, e.g. `config/` for
+/// `config/**/*.toml`.
+pub(crate) fn glob_base(pattern: &str) -> String {
+  pattern
+    .split_inclusive('/')
+    .take_while(|component| !component.contains(GLOB_CHARS))
+    .collect()
+}
+
+/// Walks the source directory of a directory or glob asset, skipping the entries that match its
+/// `exclude` patterns (relative to the directory) and, with `gitignore`, those ignored by git.
+/// Symlinks are followed (and the files they point to copied) unless the asset preserves them.
+fn walk_source_dir(asset: &Asset, source_dir_path: &Path) -> Result<ignore::Walk> {
+  let mut exclude = GlobSetBuilder::new();
+  for pattern in asset.exclude.iter().flatten() {
+    let glob = |pattern: &str| {
+      Glob::new(pattern).map_err(|e| {
+        RevolveError::Asset(format!("Invalid exclude pattern '{}': {}", pattern, e))
+      })
+    };
+    exclude.add(glob(pattern)?);
+    // `local/**` should drop the `local` directory itself, not leave it behind empty.
+    if let Some(dir) = pattern.strip_suffix("/**") {
+      exclude.add(glob(dir)?);
+    }
+  }
+  let exclude = exclude.build().context("Failed to compile the exclude patterns")?;
+
+  let root = source_dir_path.to_path_buf();
+  let mut walker = WalkBuilder::new(source_dir_path);
+  walker
+    .standard_filters(false)
+    .git_ignore(asset.gitignore)
+    .git_exclude(asset.gitignore)
+    .parents(asset.gitignore)
+    .require_git(false)
+    .follow_links(!asset.preserve_symlinks)
+    .sort_by_file_name(|a, b| a.cmp(b))
+    .filter_entry(move |entry| {
+      let relative_path = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+      !exclude.is_match(relative_path)
+    });
+  Ok(walker.build())
+}
+
+/// Records which asset provides `dest_path`, failing if another asset already does.
+fn insert_destination(
+  destination_map: &mut HashMap<PathBuf, String>,
+  dest_path: &Path,
+  asset: &Asset,
+) -> Result<()> {
+  let origin = match &asset.symlink_target {
+    Some(target) => format!("symlink to {}", target),
+    None => asset.source.clone(),
+  };
+  if let Some(existing_source) = destination_map.get(dest_path) {
+    return Err(RevolveError::DuplicateDestination {
+      dest: dest_path.display().to_string(),
+      first: format!("source '{}'", existing_source),
+      second: format!("source '{}'", origin),
+    });
+  }
+  destination_map.insert(dest_path.to_path_buf(), origin);
+  Ok(())
+}
+
+/// A symlink asset only names its target: it has no content, so a `source`, a `mode` (links are
+/// always 0777) or a `ghost`/`dir` kind would be silently ignored.
+fn validate_symlink_asset(asset: &Asset, target: &str) -> Result<()> {
+  if target.is_empty() {
+    return Err(RevolveError::Asset(format!(
+      "Asset '{}' has an empty `symlink_target`.",
+      asset.dest
+    )));
+  }
+  if !asset.source.is_empty() {
+    return Err(RevolveError::Asset(format!(
+      "Asset '{}' sets both `source` and `symlink_target`; a symlink has no source.",
+      asset.dest
+    )));
+  }
+  if asset.mode.is_some() {
+    return Err(RevolveError::Asset(format!(
+      "Asset '{}' is a symlink and cannot set a `mode`.",
+      asset.dest
+    )));
+  }
+  if matches!(asset.kind, Some(AssetKind::Ghost | AssetKind::Dir)) {
+    return Err(RevolveError::Asset(format!(
+      "Asset '{}' is a symlink and cannot be a `ghost` or `dir` asset.",
+      asset.dest
+    )));
+  }
+  Ok(())
+}
@@ -0,0 +1,339 @@
+use crate::commands::info::{DependencyInfo, FileInfo, RpmInfo, ScriptInfo};
+use crate::error::{Result, RevolveError};
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// How `diff` prints the differences.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffFormat {
+  /// One line per difference, grouped by section.
+  #[default]
+  Text,
+  /// A single JSON object, for CI jobs.
+  Json,
+}
+
+/// A kind of difference that makes `diff --fail-on` exit with an error.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffCheck {
+  /// A file of the old package is missing from the new one.
+  RemovedFiles,
+  /// The new package has a file the old one did not.
+  AddedFiles,
+  /// A file's kind, mode, owner, size, digest, link target or attributes changed.
+  ChangedFiles,
+  /// A `Requires`, `Provides`, `Conflicts` or `Obsoletes` entry was added or removed.
+  Dependencies,
+  /// A scriptlet was added, removed or changed.
+  Scripts,
+  /// Any difference at all, including header fields such as the version.
+  Any,
+}
+
+/// A value that differs between the two packages.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+  pub field: String,
+  pub old: Option<String>,
+  pub new: Option<String>,
+}
+
+impl FieldChange {
+  fn compare(field: &str, old: Option<&str>, new: Option<&str>) -> Option<Self> {
+    (old != new).then(|| FieldChange {
+      field: field.to_string(),
+      old: old.map(str::to_string),
+      new: new.map(str::to_string),
+    })
+  }
+}
+
+/// A file present in both packages with different attributes.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+  pub path: PathBuf,
+  pub changes: Vec<FieldChange>,
+}
+
+/// The entries only one of the packages has in a dependency list.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyDiff {
+  pub added: Vec<DependencyInfo>,
+  pub removed: Vec<DependencyInfo>,
+}
+
+impl DependencyDiff {
+  fn new(old: &[DependencyInfo], new: &[DependencyInfo]) -> Self {
+    DependencyDiff {
+      added: new.iter().filter(|dep| !old.contains(dep)).cloned().collect(),
+      removed: old.iter().filter(|dep| !new.contains(dep)).cloned().collect(),
+    }
+  }
+
+  fn len(&self) -> usize {
+    self.added.len() + self.removed.len()
+  }
+}
+
+/// A scriptlet that was added, removed or changed. `old` and `new` are the interpreter and body.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScriptChange {
+  pub name: String,
+  pub old: Option<ScriptInfo>,
+  pub new: Option<ScriptInfo>,
+}
+
+/// Everything that differs between two RPMs.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RpmDiff {
+  /// Header fields such as the version, release and summary.
+  pub header: Vec<FieldChange>,
+  pub added_files: Vec<FileInfo>,
+  pub removed_files: Vec<FileInfo>,
+  pub changed_files: Vec<FileChange>,
+  pub requires: DependencyDiff,
+  pub provides: DependencyDiff,
+  pub conflicts: DependencyDiff,
+  pub obsoletes: DependencyDiff,
+  pub scripts: Vec<ScriptChange>,
+}
+
+impl RpmDiff {
+  /// Compares the package `old` with `new`.
+  pub fn new(old: &RpmInfo, new: &RpmInfo) -> Self {
+    let header = [
+      ("name", Some(old.name.as_str()), Some(new.name.as_str())),
+      ("version", Some(old.version.as_str()), Some(new.version.as_str())),
+      ("release", Some(old.release.as_str()), Some(new.release.as_str())),
+      ("arch", Some(old.arch.as_str()), Some(new.arch.as_str())),
+      ("license", old.license.as_deref(), new.license.as_deref()),
+      ("summary", old.summary.as_deref(), new.summary.as_deref()),
+      ("description", old.description.as_deref(), new.description.as_deref()),
+      ("url", old.url.as_deref(), new.url.as_deref()),
+    ]
+    .into_iter()
+    .filter_map(|(field, old, new)| FieldChange::compare(field, old, new))
+    .collect();
+
+    let old_files: BTreeMap<&Path, &FileInfo> =
+      old.files.iter().map(|file| (file.path.as_path(), file)).collect();
+    let new_files: BTreeMap<&Path, &FileInfo> =
+      new.files.iter().map(|file| (file.path.as_path(), file)).collect();
+    let added_files = new_files
+      .iter()
+      .filter(|(path, _)| !old_files.contains_key(*path))
+      .map(|(_, file)| (*file).clone())
+      .collect();
+    let removed_files = old_files
+      .iter()
+      .filter(|(path, _)| !new_files.contains_key(*path))
+      .map(|(_, file)| (*file).clone())
+      .collect();
+    let changed_files = old_files
+      .iter()
+      .filter_map(|(path, old_file)| {
+        let changes = compare_files(old_file, new_files.get(path)?);
+        (!changes.is_empty()).then(|| FileChange {
+          path: path.to_path_buf(),
+          changes,
+        })
+      })
+      .collect();
+
+    RpmDiff {
+      header,
+      added_files,
+      removed_files,
+      changed_files,
+      requires: DependencyDiff::new(&old.requires, &new.requires),
+      provides: DependencyDiff::new(&old.provides, &new.provides),
+      conflicts: DependencyDiff::new(&old.conflicts, &new.conflicts),
+      obsoletes: DependencyDiff::new(&old.obsoletes, &new.obsoletes),
+      scripts: compare_scripts(&old.scripts, &new.scripts),
+    }
+  }
+
+  /// The number of differences of the kind `check` looks for.
+  pub fn count(&self, check: DiffCheck) -> usize {
+    match check {
+      DiffCheck::RemovedFiles => self.removed_files.len(),
+      DiffCheck::AddedFiles => self.added_files.len(),
+      DiffCheck::ChangedFiles => self.changed_files.len(),
+      DiffCheck::Dependencies => self.dependency_lists().iter().map(|(_, d)| d.len()).sum(),
+      DiffCheck::Scripts => self.scripts.len(),
+      DiffCheck::Any => {
+        self.header.len()
+          + self.count(DiffCheck::RemovedFiles)
+          + self.count(DiffCheck::AddedFiles)
+          + self.count(DiffCheck::ChangedFiles)
+          + self.count(DiffCheck::Dependencies)
+          + self.count(DiffCheck::Scripts)
+      }
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.count(DiffCheck::Any) == 0
+  }
+
+  fn dependency_lists(&self) -> [(&'static str, &DependencyDiff); 4] {
+    [
+      ("Requires", &self.requires),
+      ("Provides", &self.provides),
+      ("Conflicts", &self.conflicts),
+      ("Obsoletes", &self.obsoletes),
+    ]
+  }
+}
+
+/// The attributes of a file that differ between the two packages.
+fn compare_files(old: &FileInfo, new: &FileInfo) -> Vec<FieldChange> {
+  let kind = |file: &FileInfo| format!("{:?}", file.kind).to_lowercase();
+  let flags = |file: &FileInfo| file.flags.join(",");
+  [
+    FieldChange::compare("kind", Some(&kind(old)), Some(&kind(new))),
+    FieldChange::compare("mode", Some(&old.mode), Some(&new.mode)),
+    FieldChange::compare("user", Some(&old.user), Some(&new.user)),
+    FieldChange::compare("group", Some(&old.group), Some(&new.group)),
+    FieldChange::compare("size", Some(&old.size.to_string()), Some(&new.size.to_string())),
+    FieldChange::compare("digest", old.digest.as_deref(), new.digest.as_deref()),
+    FieldChange::compare("link_target", old.link_target.as_deref(), new.link_target.as_deref()),
+    FieldChange::compare("flags", Some(&flags(old)), Some(&flags(new))),
+  ]
+  .into_iter()
+  .flatten()
+  .collect()
+}
+
+fn compare_scripts(old: &[ScriptInfo], new: &[ScriptInfo]) -> Vec<ScriptChange> {
+  let find = |scripts: &[ScriptInfo], name: &str| scripts.iter().find(|s| s.name == name).cloned();
+  let added = new.iter().filter(|script| find(old, &script.name).is_none());
+  old
+    .iter()
+    .chain(added)
+    .filter_map(|script| {
+      let (old, new) = (find(old, &script.name), find(new, &script.name));
+      (old != new).then(|| ScriptChange {
+        name: script.name.clone(),
+        old,
+        new,
+      })
+    })
+    .collect()
+}
+
+/// The main entry point for the `diff` command. Fails when a difference of a kind listed in
+/// `fail_on` was found.
+pub fn run(
+  old_path: &Path,
+  new_path: &Path,
+  format: DiffFormat,
+  fail_on: &[DiffCheck],
+) -> Result<()> {
+  let old = RpmInfo::read(old_path)?;
+  let new = RpmInfo::read(new_path)?;
+  let diff = RpmDiff::new(&old, &new);
+
+  match format {
+    DiffFormat::Json => {
+      let json = serde_json::to_string_pretty(&diff).context("Failed to serialize the diff")?;
+      println!("{}", json);
+    }
+    DiffFormat::Text => print_diff(old_path, new_path, &diff),
+  }
+
+  let failures: Vec<(DiffCheck, usize)> = fail_on
+    .iter()
+    .map(|check| (*check, diff.count(*check)))
+    .filter(|(_, count)| *count > 0)
+    .collect();
+  if failures.is_empty() {
+    return Ok(());
+  }
+  let described: Vec<String> = failures
+    .iter()
+    .map(|(check, count)| format!("{} {}", count, describe_check(*check)))
+    .collect();
+  Err(RevolveError::Diff(format!(
+    "The packages differ in ways --fail-on does not allow: {}.",
+    described.join(", ")
+  )))
+}
+
+fn describe_check(check: DiffCheck) -> &'static str {
+  match check {
+    DiffCheck::RemovedFiles => "removed file(s)",
+    DiffCheck::AddedFiles => "added file(s)",
+    DiffCheck::ChangedFiles => "changed file(s)",
+    DiffCheck::Dependencies => "dependency change(s)",
+    DiffCheck::Scripts => "scriptlet change(s)",
+    DiffCheck::Any => "difference(s)",
+  }
+}
+
+fn print_diff(old_path: &Path, new_path: &Path, diff: &RpmDiff) {
+  println!("Comparing {} -> {}", old_path.display(), new_path.display());
+  if diff.is_empty() {
+    println!("\nNo differences.");
+    return;
+  }
+  let value = |value: &Option<String>| value.clone().unwrap_or_else(|| "(none)".to_string());
+
+  if !diff.header.is_empty() {
+    println!("\nHeader:");
+    for change in &diff.header {
+      println!("  {}: {} -> {}", change.field, value(&change.old), value(&change.new));
+    }
+  }
+
+  if diff.count(DiffCheck::AddedFiles)
+    + diff.count(DiffCheck::RemovedFiles)
+    + diff.count(DiffCheck::ChangedFiles)
+    > 0
+  {
+    println!("\nFiles:");
+    for file in &diff.removed_files {
+      println!("  - {}", file.path.display());
+    }
+    for file in &diff.added_files {
+      println!("  + {}", file.path.display());
+    }
+    for file in &diff.changed_files {
+      let changes: Vec<String> = file
+        .changes
+        .iter()
+        .map(|change| {
+          format!("{} {} -> {}", change.field, value(&change.old), value(&change.new))
+        })
+        .collect();
+      println!("  ~ {}: {}", file.path.display(), changes.join(", "));
+    }
+  }
+
+  for (section, dependencies) in diff.dependency_lists() {
+    if dependencies.added.is_empty() && dependencies.removed.is_empty() {
+      continue;
+    }
+    println!("\n{}:", section);
+    for dependency in &dependencies.removed {
+      println!("  - {}", dependency);
+    }
+    for dependency in &dependencies.added {
+      println!("  + {}", dependency);
+    }
+  }
+
+  if !diff.scripts.is_empty() {
+    println!("\nScripts:");
+    for change in &diff.scripts {
+      let marker = match (&change.old, &change.new) {
+        (None, _) => '+',
+        (_, None) => '-',
+        _ => '~',
+      };
+      println!("  {} {}", marker, change.name);
+    }
+  }
+}
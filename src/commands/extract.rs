@@ -0,0 +1,163 @@
+use crate::error::{Result, RevolveError};
+use anyhow::{Context, anyhow};
+use rpm::{FileFlags, FileMode, Package};
+use std::fs;
+use std::os::unix::fs::{PermissionsExt, symlink};
+use std::path::{Component, Path, PathBuf};
+
+/// Unpacks the payload of the RPM at `rpm_file_path` into `dest`, like `rpm2cpio | cpio -id`.
+///
+/// Files keep their permission bits and symlinks their targets; the owner is not changed. With
+/// a non-empty `files`, only those paths and, for directories, everything below them are
+/// extracted; they may be given with or without the leading `/`. Returns the paths that were
+/// written.
+///
+/// Nothing is written outside `dest`: an entry below a symlink, whether the payload or `dest`
+/// already had it, is refused, and a symlink in place of an entry is replaced.
+pub fn extract_package(
+  rpm_file_path: &Path,
+  dest: &Path,
+  files: &[PathBuf],
+) -> Result<Vec<PathBuf>> {
+  let package = Package::open(rpm_file_path)
+    .with_context(|| format!("Failed to open or parse RPM file at {}", rpm_file_path.display()))?;
+
+  let entries = package.metadata.get_file_entries()?;
+  let wanted: Vec<PathBuf> = files.iter().map(|file| payload_path(file)).collect();
+  for (file, wanted) in files.iter().zip(&wanted) {
+    if !entries.iter().any(|entry| payload_path(&entry.path).starts_with(wanted)) {
+      return Err(RevolveError::Config(format!(
+        "{} is not in {}",
+        file.display(),
+        rpm_file_path.display()
+      )));
+    }
+  }
+
+  fs::create_dir_all(dest)
+    .with_context(|| format!("Failed to create directory {}", dest.display()))?;
+  let mut extracted = Vec::new();
+  // Directory permissions are applied last, so read-only directories can still be filled.
+  let mut dirs = Vec::new();
+  for file in package.files()? {
+    let file = file?;
+    let entry = &file.metadata;
+    if entry.flags.contains(FileFlags::GHOST) {
+      continue;
+    }
+    let entry_path = payload_path(&entry.path);
+    if !wanted.is_empty() && !wanted.iter().any(|wanted| entry_path.starts_with(wanted)) {
+      continue;
+    }
+
+    let relative = relative_path(&entry.path)?;
+    check_parents(dest, &relative)?;
+    let path = dest.join(&relative);
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)
+        .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    // Writing through a symlink that is already there would change its target instead.
+    if path.symlink_metadata().is_ok_and(|metadata| metadata.is_symlink()) {
+      fs::remove_file(&path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    }
+    let permissions = fs::Permissions::from_mode(entry.mode.permissions().into());
+    match entry.mode {
+      FileMode::Dir { .. } => {
+        fs::create_dir_all(&path)
+          .with_context(|| format!("Failed to create directory {}", path.display()))?;
+        dirs.push((path.clone(), permissions));
+      }
+      FileMode::Regular { .. } => {
+        fs::write(&path, &file.content)
+          .with_context(|| format!("Failed to write {}", path.display()))?;
+        fs::set_permissions(&path, permissions)
+          .with_context(|| format!("Failed to set the mode of {}", path.display()))?;
+      }
+      FileMode::SymbolicLink { .. } => {
+        if path.symlink_metadata().is_ok() {
+          fs::remove_file(&path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+        }
+        symlink(&entry.linkto, &path)
+          .with_context(|| format!("Failed to create symlink {}", path.display()))?;
+      }
+      _ => {
+        log::warn!("Skipping {}, which is not a file, directory or symlink", entry.path.display());
+        continue;
+      }
+    }
+    log::debug!("Extracted {}", path.display());
+    extracted.push(path);
+  }
+
+  for (path, permissions) in dirs.into_iter().rev() {
+    fs::set_permissions(&path, permissions)
+      .with_context(|| format!("Failed to set the mode of {}", path.display()))?;
+  }
+  Ok(extracted)
+}
+
+/// The path of a payload entry relative to the extraction directory. Refuses paths that would
+/// escape it.
+fn relative_path(path: &Path) -> Result<PathBuf> {
+  let mut relative = PathBuf::new();
+  for component in path.components() {
+    match component {
+      Component::RootDir | Component::CurDir => {}
+      Component::Normal(name) => relative.push(name),
+      Component::ParentDir | Component::Prefix(_) => {
+        return Err(
+          anyhow!("Refusing to extract {}, which points outside the destination", path.display())
+            .into(),
+        );
+      }
+    }
+  }
+  Ok(relative)
+}
+
+/// Refuses to extract `relative` when one of its parent directories below `dest` is a symlink,
+/// which could point anywhere, e.g. an earlier `/a -> /` entry for `/a/etc/passwd`.
+fn check_parents(dest: &Path, relative: &Path) -> Result<()> {
+  let mut parent = dest.to_path_buf();
+  for component in relative.parent().into_iter().flat_map(Path::components) {
+    parent.push(component);
+    match parent.symlink_metadata() {
+      Ok(metadata) if metadata.is_symlink() => {
+        return Err(
+          anyhow!(
+            "Refusing to extract {} through the symlink {}",
+            relative.display(),
+            parent.display()
+          )
+          .into(),
+        );
+      }
+      Ok(_) => {}
+      // Missing directories are created, and cannot be symlinks below this one either.
+      Err(_) => break,
+    }
+  }
+  Ok(())
+}
+
+/// `path` as it appears in the payload, with a leading `/`.
+fn payload_path(path: &Path) -> PathBuf {
+  Path::new("/").join(path)
+}
+
+/// The main entry point for the `extract` command. Without `to`, the payload is unpacked into a
+/// directory named after the RPM file, e.g. `my-app-0.1.0-1.x86_64/`.
+pub fn run(rpm_file_path: &Path, to: Option<&Path>, files: &[PathBuf]) -> Result<()> {
+  let dest = match to {
+    Some(to) => to.to_path_buf(),
+    None => PathBuf::from(rpm_file_path.file_stem().with_context(|| {
+      format!("Cannot name a directory after {}", rpm_file_path.display())
+    })?),
+  };
+
+  let extracted = extract_package(rpm_file_path, &dest, files)?;
+  println!("Extracted {} file(s) to {}", extracted.len(), dest.display());
+  Ok(())
+}
@@ -1,40 +1,467 @@
 use crate::error::Result;
 use anyhow::Context;
-use rpm::Package;
-use std::path::Path;
+use rpm::{DependencyFlags, FileFlags, FileMode, Package, Scriptlet};
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// How `info` prints the package.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InfoFormat {
+  /// Aligned text for people, or one entry per line for queries.
+  #[default]
+  Table,
+  Json,
+  Yaml,
+}
+
+/// The parts of the package `info` prints. Without any, it prints a summary and the file list.
+#[derive(clap::Args, Debug, Clone, Copy, Default)]
+pub struct InfoQuery {
+  /// Print the capabilities the package requires.
+  #[arg(long)]
+  pub requires: bool,
+  /// Print the capabilities the package provides.
+  #[arg(long)]
+  pub provides: bool,
+  /// Print the packages this package conflicts with.
+  #[arg(long)]
+  pub conflicts: bool,
+  /// Print the packages this package obsoletes.
+  #[arg(long)]
+  pub obsoletes: bool,
+  /// Print the install and uninstall scriptlets.
+  #[arg(long)]
+  pub scripts: bool,
+  /// Print the changelog.
+  #[arg(long)]
+  pub changelog: bool,
+  /// Print the file list.
+  #[arg(long)]
+  pub files: bool,
+  /// With `--files`, also print each file's mode, owner, size and digest.
+  #[arg(long, requires = "files")]
+  pub long: bool,
+}
+
+impl InfoQuery {
+  fn is_empty(&self) -> bool {
+    !(self.requires
+      || self.provides
+      || self.conflicts
+      || self.obsoletes
+      || self.scripts
+      || self.changelog
+      || self.files)
+  }
+}
+
+/// The header fields, dependencies, scriptlets, changelog and file list of an RPM file.
+#[derive(Serialize, Debug, Clone)]
+pub struct RpmInfo {
+  pub name: String,
+  pub version: String,
+  pub release: String,
+  pub arch: String,
+  /// The installed size in bytes.
+  pub installed_size: u64,
+  pub license: Option<String>,
+  pub summary: Option<String>,
+  pub description: Option<String>,
+  pub url: Option<String>,
+  pub requires: Vec<DependencyInfo>,
+  pub provides: Vec<DependencyInfo>,
+  pub conflicts: Vec<DependencyInfo>,
+  pub obsoletes: Vec<DependencyInfo>,
+  /// The scriptlets present in the package, in the order they run.
+  pub scripts: Vec<ScriptInfo>,
+  /// The changelog, newest entry first.
+  pub changelog: Vec<ChangelogInfo>,
+  pub files: Vec<FileInfo>,
+}
+
+/// A `Requires`, `Provides`, `Conflicts` or `Obsoletes` entry.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DependencyInfo {
+  pub name: String,
+  /// The version comparison, e.g. `>=`, if the dependency has a version.
+  pub op: Option<String>,
+  pub version: Option<String>,
+}
+
+impl DependencyInfo {
+  fn new(dependency: rpm::Dependency) -> Self {
+    let op = match dependency.flags & (DependencyFlags::LE | DependencyFlags::GE) {
+      DependencyFlags::LE => Some("<="),
+      DependencyFlags::GE => Some(">="),
+      DependencyFlags::LESS => Some("<"),
+      DependencyFlags::GREATER => Some(">"),
+      DependencyFlags::EQUAL => Some("="),
+      _ => None,
+    };
+    DependencyInfo {
+      name: dependency.name,
+      op: op.map(str::to_string),
+      version: Some(dependency.version).filter(|version| !version.is_empty()),
+    }
+  }
+}
+
+impl fmt::Display for DependencyInfo {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.name)?;
+    if let (Some(op), Some(version)) = (&self.op, &self.version) {
+      write!(f, " {} {}", op, version)?;
+    }
+    Ok(())
+  }
+}
+
+/// An install or uninstall scriptlet.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScriptInfo {
+  /// The scriptlet, named like the `scripts` config keys: `pre`, `post`, `preun`, ...
+  pub name: String,
+  /// The program that runs it, `/bin/sh` unless the package says otherwise.
+  pub interpreter: String,
+  pub body: String,
+}
+
+/// One `%changelog` entry.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChangelogInfo {
+  /// The date of the entry as a UNIX timestamp.
+  pub timestamp: u64,
+  /// The author line, e.g. `Jane Doe <jane@example.com> - 1.0.0-1`.
+  pub author: String,
+  pub text: String,
+}
+
+/// What kind of file system entry a file is.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+  File,
+  Dir,
+  Symlink,
+  Other,
+}
+
+/// A file, directory or symlink installed by the package.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileInfo {
+  pub path: PathBuf,
+  pub kind: FileKind,
+  /// The permission bits in octal, e.g. `0644`.
+  pub mode: String,
+  pub user: String,
+  pub group: String,
+  pub size: u64,
+  /// The hex-encoded digest of a regular file's contents, usually SHA-256.
+  pub digest: Option<String>,
+  pub link_target: Option<String>,
+  /// The `%files` attributes, e.g. `config` and `noreplace`.
+  pub flags: Vec<String>,
+}
+
+/// The `%files` attributes reported for a file, by their `%files` directive names.
+const FILE_FLAG_NAMES: [(FileFlags, &str); 8] = [
+  (FileFlags::CONFIG, "config"),
+  (FileFlags::NOREPLACE, "noreplace"),
+  (FileFlags::MISSINGOK, "missingok"),
+  (FileFlags::DOC, "doc"),
+  (FileFlags::LICENSE, "license"),
+  (FileFlags::README, "readme"),
+  (FileFlags::GHOST, "ghost"),
+  (FileFlags::ARTIFACT, "artifact"),
+];
+
+impl FileInfo {
+  fn new(entry: rpm::FileEntry) -> Self {
+    let kind = match entry.mode {
+      FileMode::Regular { .. } => FileKind::File,
+      FileMode::Dir { .. } => FileKind::Dir,
+      FileMode::SymbolicLink { .. } => FileKind::Symlink,
+      _ => FileKind::Other,
+    };
+    FileInfo {
+      path: entry.path,
+      kind,
+      mode: format!("{:04o}", entry.mode.permissions()),
+      user: entry.ownership.user,
+      group: entry.ownership.group,
+      size: entry.size as u64,
+      // Directories and symlinks carry the digest of no content at all.
+      digest: entry
+        .digest
+        .filter(|_| kind == FileKind::File)
+        .map(|digest| digest.digest)
+        .filter(|digest| !digest.is_empty()),
+      link_target: Some(entry.linkto).filter(|target| !target.is_empty()),
+      flags: FILE_FLAG_NAMES
+        .iter()
+        .filter(|(flag, _)| entry.flags.contains(*flag))
+        .map(|(_, name)| name.to_string())
+        .collect(),
+    }
+  }
+
+  /// The mode as `ls -l` prints it, e.g. `-rwxr-xr-x`.
+  fn mode_string(&self) -> String {
+    let mut mode = String::from(match self.kind {
+      FileKind::File => '-',
+      FileKind::Dir => 'd',
+      FileKind::Symlink => 'l',
+      FileKind::Other => '?',
+    });
+    let permissions = u16::from_str_radix(&self.mode, 8).unwrap_or_default();
+    for shift in [6, 3, 0] {
+      let bits = permissions >> shift;
+      mode.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+      mode.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+      mode.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+    mode
+  }
+}
+
+impl RpmInfo {
+  /// Reads the header of the RPM file at `path`.
+  pub fn read(path: &Path) -> Result<Self> {
+    let package = Package::open(path)
+      .with_context(|| format!("Failed to open or parse RPM file at {}", path.display()))?;
+    let metadata = &package.metadata;
+    let dependencies = |list: Vec<rpm::Dependency>| -> Vec<DependencyInfo> {
+      list.into_iter().map(DependencyInfo::new).collect()
+    };
+
+    let scriptlets = [
+      ("pretrans", metadata.get_pre_trans_script()),
+      ("pre", metadata.get_pre_install_script()),
+      ("post", metadata.get_post_install_script()),
+      ("preun", metadata.get_pre_uninstall_script()),
+      ("postun", metadata.get_post_uninstall_script()),
+      ("posttrans", metadata.get_post_trans_script()),
+    ];
+    let scripts = scriptlets
+      .into_iter()
+      .filter_map(|(name, scriptlet)| Some(script_info(name, scriptlet.ok()?)))
+      .collect();
+
+    let changelog = metadata
+      .get_changelog_entries()
+      .unwrap_or_default()
+      .into_iter()
+      .map(|entry| ChangelogInfo {
+        timestamp: entry.timestamp,
+        author: entry.name,
+        text: entry.description,
+      })
+      .collect();
+
+    Ok(RpmInfo {
+      name: metadata.get_name()?.to_string(),
+      version: metadata.get_version()?.to_string(),
+      release: metadata.get_release()?.to_string(),
+      arch: metadata.get_arch()?.to_string(),
+      installed_size: metadata.get_installed_size()?,
+      license: metadata.get_license().ok().map(str::to_string),
+      summary: metadata.get_summary().ok().map(str::to_string),
+      description: metadata.get_description().ok().map(str::to_string),
+      url: metadata.get_url().ok().map(str::to_string),
+      requires: dependencies(metadata.get_requires()?),
+      provides: dependencies(metadata.get_provides()?),
+      conflicts: dependencies(metadata.get_conflicts()?),
+      obsoletes: dependencies(metadata.get_obsoletes()?),
+      scripts,
+      changelog,
+      files: metadata.get_file_entries()?.into_iter().map(FileInfo::new).collect(),
+    })
+  }
+}
+
+fn script_info(name: &str, scriptlet: Scriptlet) -> ScriptInfo {
+  let interpreter = scriptlet
+    .program
+    .filter(|program| !program.is_empty())
+    .map_or_else(|| "/bin/sh".to_string(), |program| program.join(" "));
+  ScriptInfo {
+    name: name.to_string(),
+    interpreter,
+    body: scriptlet.script,
+  }
+}
+
+/// The file list printed for a query: just the paths, or everything with `--long`.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum FilesView<'a> {
+  Paths(Vec<&'a Path>),
+  Long(&'a [FileInfo]),
+}
+
+/// The sections selected by a query, for the JSON and YAML formats.
+#[derive(Serialize)]
+struct QueryView<'a> {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  requires: Option<&'a [DependencyInfo]>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  provides: Option<&'a [DependencyInfo]>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  conflicts: Option<&'a [DependencyInfo]>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  obsoletes: Option<&'a [DependencyInfo]>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  scripts: Option<&'a [ScriptInfo]>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  changelog: Option<&'a [ChangelogInfo]>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  files: Option<FilesView<'a>>,
+}
+
+impl<'a> QueryView<'a> {
+  fn new(info: &'a RpmInfo, query: &InfoQuery) -> Self {
+    let files = match (query.files, query.long) {
+      (false, _) => None,
+      (true, false) => Some(FilesView::Paths(info.files.iter().map(|f| f.path.as_path()).collect())),
+      (true, true) => Some(FilesView::Long(&info.files)),
+    };
+    QueryView {
+      requires: query.requires.then_some(info.requires.as_slice()),
+      provides: query.provides.then_some(info.provides.as_slice()),
+      conflicts: query.conflicts.then_some(info.conflicts.as_slice()),
+      obsoletes: query.obsoletes.then_some(info.obsoletes.as_slice()),
+      scripts: query.scripts.then_some(info.scripts.as_slice()),
+      changelog: query.changelog.then_some(info.changelog.as_slice()),
+      files,
+    }
+  }
+}
 
 /// The main entry point for the `info` command.
-pub fn run(rpm_file_path: &Path) -> Result<()> {
-  println!("Inspecting: {}", rpm_file_path.display());
+pub fn run(rpm_file_path: &Path, format: InfoFormat, query: &InfoQuery) -> Result<()> {
+  let info = RpmInfo::read(rpm_file_path)?;
+
+  match format {
+    InfoFormat::Json => {
+      let json = if query.is_empty() {
+        serde_json::to_string_pretty(&info)
+      } else {
+        serde_json::to_string_pretty(&QueryView::new(&info, query))
+      };
+      println!("{}", json.context("Failed to serialize the package information")?);
+    }
+    InfoFormat::Yaml => {
+      let yaml = if query.is_empty() {
+        serde_norway::to_string(&info)
+      } else {
+        serde_norway::to_string(&QueryView::new(&info, query))
+      };
+      print!("{}", yaml.context("Failed to serialize the package information")?);
+    }
+    InfoFormat::Table if query.is_empty() => print_summary(rpm_file_path, &info),
+    InfoFormat::Table => print_query(&info, query),
+  }
 
-  let package = Package::open(rpm_file_path)
-    .with_context(|| format!("Failed to open or parse RPM file at {}", rpm_file_path.display()))?;
+  Ok(())
+}
 
-  // Extract and print header information from `package.metadata`
-  let metadata = &package.metadata;
-  let name = metadata.get_name()?;
-  let version = metadata.get_version()?;
-  let release = metadata.get_release()?;
-  let arch = metadata.get_arch()?;
-  let size = metadata.get_installed_size()?;
-  let license = metadata.get_license().unwrap_or("N/A");
-  let summary = metadata.get_summary().unwrap_or("N/A");
+fn print_summary(rpm_file_path: &Path, info: &RpmInfo) {
+  println!("Inspecting: {}", rpm_file_path.display());
 
   println!("\nPackage Summary:");
-  println!("  Name:      {}", name);
-  println!("  Version:   {}", version);
-  println!("  Release:   {}", release);
-  println!("  Arch:      {}", arch);
-  println!("  Size:      {} bytes (installed)", size);
-  println!("  License:   {}", license);
-  println!("  Summary:   {}", summary);
-  
-  // Extract and print file list
-  let file_paths = metadata.get_file_paths()?;
-  println!("\nFiles ({}):", file_paths.len());
-  for path in file_paths {
-      println!("  {}", path.display());
+  println!("  Name:      {}", info.name);
+  println!("  Version:   {}", info.version);
+  println!("  Release:   {}", info.release);
+  println!("  Arch:      {}", info.arch);
+  println!("  Size:      {} bytes (installed)", info.installed_size);
+  println!("  License:   {}", info.license.as_deref().unwrap_or("N/A"));
+  println!("  Summary:   {}", info.summary.as_deref().unwrap_or("N/A"));
+
+  println!("\nFiles ({}):", info.files.len());
+  for file in &info.files {
+      println!("  {}", file.path.display());
   }
+}
 
-  Ok(())
-}
\ No newline at end of file
+/// Prints the selected sections one entry per line, like `rpm -qp` does, so they are easy to
+/// process with other tools.
+fn print_query(info: &RpmInfo, query: &InfoQuery) {
+  let dependency_lists = [
+    (query.requires, &info.requires),
+    (query.provides, &info.provides),
+    (query.conflicts, &info.conflicts),
+    (query.obsoletes, &info.obsoletes),
+  ];
+  for (_, dependencies) in dependency_lists.iter().filter(|(selected, _)| *selected) {
+    for dependency in dependencies.iter() {
+      println!("{}", dependency);
+    }
+  }
+
+  if query.scripts {
+    for script in &info.scripts {
+      println!("{} scriptlet (using {}):", script.name, script.interpreter);
+      println!("{}", script.body.trim_end());
+    }
+  }
+
+  if query.changelog {
+    for entry in &info.changelog {
+      println!("* {} {}", format_changelog_date(entry.timestamp), entry.author);
+      println!("{}\n", entry.text.trim_end());
+    }
+  }
+
+  if query.files {
+    let size_width = info.files.iter().map(|f| f.size.to_string().len()).max().unwrap_or(1);
+    for file in &info.files {
+      if !query.long {
+        println!("{}", file.path.display());
+        continue;
+      }
+      let mut line = format!(
+        "{} {:<8} {:<8} {:>size_width$} {:<64} {}",
+        file.mode_string(),
+        file.user,
+        file.group,
+        file.size,
+        file.digest.as_deref().unwrap_or("-"),
+        file.path.display()
+      );
+      if let Some(target) = &file.link_target {
+        line.push_str(&format!(" -> {}", target));
+      }
+      println!("{}", line);
+    }
+  }
+}
+
+/// Formats a changelog timestamp as the date of a `.spec` changelog header, e.g.
+/// `Mon Jan 01 2024`.
+fn format_changelog_date(timestamp: u64) -> String {
+  const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+  const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+  ];
+  let days = (timestamp / 86_400) as i64;
+
+  // The proleptic Gregorian date of a day since the epoch (Howard Hinnant's algorithm).
+  let z = days + 719_468;
+  let era = z.div_euclid(146_097);
+  let doe = z - era * 146_097;
+  let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + i64::from(month <= 2);
+
+  format!(
+    "{} {} {:02} {}",
+    WEEKDAYS[days.rem_euclid(7) as usize],
+    MONTHS[(month - 1) as usize],
+    day,
+    year
+  )
+}
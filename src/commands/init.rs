@@ -0,0 +1,316 @@
+use crate::error::{Result, RevolveError};
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use cargo_metadata::{Package as CargoPackage, TargetKind};
+use ignore::WalkBuilder;
+use toml_edit::{Array, DocumentMut, InlineTable, Item, Table, Value};
+
+/// The spec template written by `init`. `@SOURCE@`, `@PREP@` and `@INSTALL_FROM@` are filled in
+/// for the archive or the `--no-archive` flavour.
+const SPEC_TEMPLATE: &str = r#"# Generated by `cargo revolve init`; adjust it to your package as needed.
+%define debug_package %{nil}
+
+Name:           {{ pkg.name }}
+Version:        {{ pkg.version }}
+Release:        1%{?dist}
+Summary:        {{ pkg.description }}
+License:        {{ pkg.license }}
+@SOURCE@{% for dep in builder.build_requires %}
+BuildRequires:  {{ dep.spec }}
+{% endfor %}
+{% for dep in builder.requires %}
+Requires:       {{ dep.spec }}
+{% endfor %}
+{% for dep in builder.auto_requires %}
+Requires:       {{ dep.spec }}
+{% endfor %}
+
+%description
+{{ pkg.description }}
+
+%prep
+@PREP@
+%build
+# Empty: cargo-revolve compiles the project before rpmbuild runs.
+
+%install
+rm -rf %{buildroot}
+{% for asset in builder.assets %}
+{% if asset.kind == "dir" %}
+install -d -m {{ asset.mode | default(value="0755") }} "%{buildroot}{{ asset.dest }}"
+{% elif asset.symlink_target %}
+install -d "$(dirname "%{buildroot}{{ asset.dest }}")"
+ln -s "{{ asset.symlink_target }}" "%{buildroot}{{ asset.dest }}"
+{% elif asset.kind == "ghost" %}
+install -D -m {{ asset.mode | default(value="0644") }} /dev/null "%{buildroot}{{ asset.dest }}"
+{% else %}
+install -D -m {{ asset.mode | default(value="0644") }} "@INSTALL_FROM@" "%{buildroot}{{ asset.dest }}"
+{% endif %}
+{% endfor %}
+
+{% if builder.sysusers.file %}
+%pre
+{{ builder.sysusers.pre }}
+{% endif %}
+
+{% if builder.systemd.units %}
+%post
+{{ builder.systemd.post }}
+
+%preun
+{{ builder.systemd.preun }}
+
+%postun
+{{ builder.systemd.postun }}
+{% endif %}
+
+%files
+%defattr(-, root, root, -)
+{% for dir in builder.created_dirs %}
+%dir {{ dir }}
+{% endfor %}
+{% for asset in builder.assets %}
+{% if asset.kind == "config_noreplace" %}%config(noreplace) {% elif asset.kind %}%{{ asset.kind }} {% endif %}{% if asset.mode or asset.user or asset.group %}%attr({{ asset.mode | default(value="-") }}, {{ asset.user | default(value="-") }}, {{ asset.group | default(value="-") }}) {% endif %}{{ asset.dest }}
+{% endfor %}
+
+{% if builder.changelog %}
+%changelog
+{{ builder.changelog | trim }}
+{% endif %}
+"#;
+
+/// What `init` found in the package directory.
+#[derive(Debug, Default)]
+struct Detected {
+  binaries: Vec<String>,
+  units: Vec<String>,
+  has_config_dir: bool,
+  licenses: Vec<String>,
+  docs: Vec<String>,
+}
+
+/// The main entry point for the `init` command.
+pub fn run(package: &CargoPackage, no_archive: bool) -> Result<()> {
+  let manifest_path = package.manifest_path.as_std_path();
+  let manifest_dir = manifest_path.parent().unwrap();
+
+  let manifest_content = fs::read_to_string(manifest_path)
+    .with_context(|| format!("Failed to read manifest file at {}", manifest_path.display()))?;
+  let mut manifest: DocumentMut = manifest_content
+    .parse()
+    .with_context(|| format!("Failed to parse {}", manifest_path.display()))?;
+  if package.metadata.get("revolve").is_some() {
+    return Err(RevolveError::Config(format!(
+      "{} already has a `[package.metadata.revolve]` table",
+      manifest_path.display()
+    )));
+  }
+
+  let spec_template = format!(".revolve/{}.spec.in", package.name);
+  let spec_path = manifest_dir.join(&spec_template);
+  if spec_path.exists() {
+    return Err(RevolveError::Config(format!(
+      "Spec template {} already exists; remove it or move it out of the way first.",
+      spec_path.display()
+    )));
+  }
+
+  let detected = detect(package, manifest_dir)?;
+  let revolve_table = revolve_table(package, &spec_template, &detected);
+  insert_revolve_table(&mut manifest, revolve_table)
+    .with_context(|| format!("Failed to update {}", manifest_path.display()))?;
+
+  fs::create_dir_all(spec_path.parent().unwrap())?;
+  fs::write(&spec_path, render_template(no_archive))
+    .with_context(|| format!("Failed to write spec template to {}", spec_path.display()))?;
+  fs::write(manifest_path, manifest.to_string())
+    .with_context(|| format!("Failed to write manifest file at {}", manifest_path.display()))?;
+
+  println!("Created spec template: {}", spec_path.display());
+  println!("Added `[package.metadata.revolve]` to {}", manifest_path.display());
+  for binary in &detected.binaries {
+    println!("  binary:       {}", binary);
+  }
+  for unit in &detected.units {
+    println!("  systemd unit: {}", unit);
+  }
+  if detected.has_config_dir {
+    println!("  config:       config/");
+  }
+  for file in detected.licenses.iter().chain(&detected.docs) {
+    println!("  file:         {}", file);
+  }
+  let build_flags = if no_archive { " --no-archive" } else { "" };
+  println!("\nReview both files, then run `cargo revolve build{} --verify`.", build_flags);
+
+  Ok(())
+}
+
+/// The spec template for the chosen flavour.
+fn render_template(no_archive: bool) -> String {
+  let (source, prep, install_from) = if no_archive {
+    // Without an archive rpmbuild reads the files straight from the project and target
+    // directories; `asset.source_path` also follows a relocated Cargo target directory.
+    ("", "", "{{ asset.source_path }}")
+  } else {
+    (
+      "Source0:        {{ builder.archive_filename }}\n",
+      "%setup -q -n {{ builder.archive_root_dir }}\n",
+      "{{ asset.archive_path }}",
+    )
+  };
+  SPEC_TEMPLATE
+    .replace("@SOURCE@", source)
+    .replace("@PREP@", prep)
+    .replace("@INSTALL_FROM@", install_from)
+}
+
+/// Looks for the binary targets, systemd units, `config/` directory and license and readme
+/// files of the package.
+fn detect(package: &CargoPackage, manifest_dir: &Path) -> Result<Detected> {
+  let mut detected = Detected {
+    binaries: package
+      .targets
+      .iter()
+      .filter(|target| target.is_kind(TargetKind::Bin))
+      .map(|target| target.name.clone())
+      .collect(),
+    has_config_dir: manifest_dir.join("config").is_dir(),
+    ..Default::default()
+  };
+  detected.binaries.sort();
+
+  for entry in fs::read_dir(manifest_dir)? {
+    let entry = entry?;
+    if !entry.file_type()?.is_file() {
+      continue;
+    }
+    let name = entry.file_name().to_string_lossy().into_owned();
+    let upper = name.to_uppercase();
+    if upper.starts_with("LICENSE") || upper.starts_with("LICENCE") || upper.starts_with("COPYING") {
+      detected.licenses.push(name);
+    } else if upper.starts_with("README") {
+      detected.docs.push(name);
+    }
+  }
+  detected.licenses.sort();
+  detected.docs.sort();
+
+  // Units can live anywhere in the project, but never in the build output.
+  let walker = WalkBuilder::new(manifest_dir)
+    .require_git(false)
+    .sort_by_file_name(|a, b| a.cmp(b))
+    .filter_entry(|entry| entry.file_name() != "target")
+    .build();
+  for entry in walker {
+    let entry = entry.context("Failed to search for systemd units")?;
+    let path = entry.path();
+    if entry.file_type().is_some_and(|t| t.is_file())
+      && path.extension().is_some_and(|ext| ext == "service")
+    {
+      detected.units.push(relative_path(path, manifest_dir));
+    }
+  }
+
+  Ok(detected)
+}
+
+/// `path` relative to `base`, with `/` separators as used in `Cargo.toml`.
+fn relative_path(path: &Path, base: &Path) -> String {
+  path
+    .strip_prefix(base)
+    .unwrap_or(path)
+    .components()
+    .map(|c| c.as_os_str().to_string_lossy())
+    .collect::<Vec<_>>()
+    .join("/")
+}
+
+/// Builds the `[package.metadata.revolve]` table for the detected files.
+fn revolve_table(package: &CargoPackage, spec_template: &str, detected: &Detected) -> Table {
+  let name = package.name.as_str();
+  let mut assets = Vec::new();
+  for binary in &detected.binaries {
+    let mut binary_asset = asset(&[
+      ("source", &format!("target/release/{}", binary)),
+      ("dest", &format!("/usr/bin/{}", binary)),
+      ("mode", "0755"),
+    ]);
+    // `/usr/bin` belongs to the filesystem package.
+    binary_asset.insert("mkdir", Value::from(false));
+    assets.push(Value::InlineTable(binary_asset));
+  }
+  if detected.has_config_dir {
+    assets.push(Value::InlineTable(asset(&[
+      ("source", "config/"),
+      ("dest", &format!("/etc/{}/", name)),
+      ("kind", "config_noreplace"),
+    ])));
+  }
+  for file in &detected.licenses {
+    assets.push(Value::InlineTable(asset(&[
+      ("source", file),
+      ("dest", &format!("/usr/share/licenses/{}/{}", name, file)),
+      ("kind", "license"),
+    ])));
+  }
+  for file in &detected.docs {
+    assets.push(Value::InlineTable(asset(&[
+      ("source", file),
+      ("dest", &format!("/usr/share/doc/{}/{}", name, file)),
+      ("kind", "doc"),
+    ])));
+  }
+
+  let mut table = Table::new();
+  table.insert("spec_template", toml_edit::value(spec_template));
+  table.insert("output_dir", toml_edit::value("dist"));
+  table.insert("assets", toml_edit::value(multiline_array(assets)));
+  if !detected.units.is_empty() {
+    let units = detected.units.iter().map(|unit| Value::from(unit.as_str()));
+    table.insert("systemd_units", toml_edit::value(Array::from_iter(units)));
+  }
+  table
+}
+
+fn asset(fields: &[(&str, &str)]) -> InlineTable {
+  let mut asset = InlineTable::new();
+  for (key, value) in fields {
+    asset.insert(*key, Value::from(*value));
+  }
+  asset
+}
+
+/// An array with one value per line, the way the assets are written in the README.
+fn multiline_array(values: Vec<Value>) -> Array {
+  let mut array = Array::new();
+  for mut value in values {
+    value.decor_mut().set_prefix("\n  ");
+    array.push_formatted(value);
+  }
+  array.set_trailing_comma(true);
+  array.set_trailing("\n");
+  array
+}
+
+/// Adds `revolve` below `[package.metadata]`, creating that table if needed, and leaves the rest
+/// of the document as it was.
+fn insert_revolve_table(manifest: &mut DocumentMut, revolve: Table) -> anyhow::Result<()> {
+  let Some(package) = manifest.get_mut("package").and_then(Item::as_table_mut) else {
+    anyhow::bail!("Expected a `[package]` table");
+  };
+  let metadata = package.entry("metadata").or_insert_with(|| {
+    let mut metadata = Table::new();
+    // Only `[package.metadata.revolve]` is written, not an empty `[package.metadata]`.
+    metadata.set_implicit(true);
+    Item::Table(metadata)
+  });
+  let Some(metadata) = metadata.as_table_like_mut() else {
+    anyhow::bail!("`package.metadata` must be a table");
+  };
+  metadata.insert("revolve", Item::Table(revolve));
+  Ok(())
+}
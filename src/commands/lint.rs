@@ -0,0 +1,754 @@
+use crate::arch;
+use crate::commands::build::{self, ExpandedConfig, GLOB_CHARS, PackagePlan, ResolvedConfig};
+use crate::config::{Asset, AssetKind, RevolveConfig};
+use crate::definitions::{BUILDER_FIELDS, PKG_FIELDS};
+use crate::error::{Result, RevolveError, chain_message};
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use cargo_metadata::Package as CargoPackage;
+use globset::{GlobBuilder, GlobMatcher};
+use serde::Serialize;
+use serde_json::Value;
+
+/// How `lint` prints its findings.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintFormat {
+  /// One line per issue.
+  Text,
+  /// A single JSON object, for CI jobs and editors.
+  Json,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+  /// The build would fail or produce a broken package.
+  Error,
+  /// Most likely a mistake, but the package can still be built.
+  Warning,
+}
+
+/// A single finding. `check` names the rule that produced it, e.g. `mode` or `files`.
+#[derive(Serialize, Debug)]
+pub struct Issue {
+  pub severity: Severity,
+  pub check: &'static str,
+  pub message: String,
+}
+
+#[derive(Serialize)]
+struct LintReport<'a> {
+  package: &'a str,
+  version: String,
+  errors: usize,
+  warnings: usize,
+  issues: &'a [Issue],
+}
+
+/// Directories that belong to the base system (or to systemd), so assets installed into them
+/// can safely set `mkdir = false`.
+const SYSTEM_DIRS: &[&str] = &[
+  "/",
+  "/etc",
+  "/etc/cron.d",
+  "/etc/logrotate.d",
+  "/etc/profile.d",
+  "/etc/sudoers.d",
+  "/etc/sysconfig",
+  "/etc/systemd/system",
+  "/opt",
+  "/run",
+  "/srv",
+  "/usr",
+  "/usr/bin",
+  "/usr/include",
+  "/usr/lib",
+  "/usr/lib/systemd/system",
+  "/usr/lib/systemd/user",
+  "/usr/lib/sysusers.d",
+  "/usr/lib/tmpfiles.d",
+  "/usr/lib/udev/rules.d",
+  "/usr/lib64",
+  "/usr/libexec",
+  "/usr/sbin",
+  "/usr/share",
+  "/usr/share/applications",
+  "/usr/share/doc",
+  "/usr/share/licenses",
+  "/usr/share/man",
+  "/usr/share/man/man1",
+  "/usr/share/man/man5",
+  "/usr/share/man/man8",
+  "/var",
+  "/var/cache",
+  "/var/lib",
+  "/var/log",
+];
+
+/// Section headers of a .spec file. Each one ends the section before it.
+const SPEC_SECTIONS: &[&str] = &[
+  "package",
+  "description",
+  "prep",
+  "generate_buildrequires",
+  "conf",
+  "build",
+  "install",
+  "check",
+  "clean",
+  "files",
+  "changelog",
+  "pre",
+  "post",
+  "preun",
+  "postun",
+  "pretrans",
+  "posttrans",
+  "preuntrans",
+  "postuntrans",
+  "triggerprein",
+  "triggerin",
+  "triggerun",
+  "triggerpostun",
+  "filetriggerin",
+  "filetriggerun",
+  "filetriggerpostun",
+  "transfiletriggerin",
+  "transfiletriggerun",
+  "transfiletriggerpostun",
+  "verifyscript",
+];
+
+/// Directives that may precede a path in `%files`, e.g. `%config(noreplace)`.
+const FILE_DIRECTIVES: &[&str] = &[
+  "artifact",
+  "attr",
+  "caps",
+  "config",
+  "dir",
+  "doc",
+  "ghost",
+  "lang",
+  "license",
+  "missingok",
+  "readme",
+  "verify",
+];
+
+/// The usual values of the path macros, so `%files` entries can be compared with asset
+/// destinations. Other macros, like the architecture dependent `%{_libdir}`, match anything.
+const PATH_MACROS: &[(&str, &str)] = &[
+  ("_prefix", "/usr"),
+  ("_exec_prefix", "/usr"),
+  ("_bindir", "/usr/bin"),
+  ("_sbindir", "/usr/sbin"),
+  ("_libexecdir", "/usr/libexec"),
+  ("_datadir", "/usr/share"),
+  ("_sysconfdir", "/etc"),
+  ("_localstatedir", "/var"),
+  ("_sharedstatedir", "/var/lib"),
+  ("_includedir", "/usr/include"),
+  ("_docdir", "/usr/share/doc"),
+  ("_defaultdocdir", "/usr/share/doc"),
+  ("_defaultlicensedir", "/usr/share/licenses"),
+  ("_mandir", "/usr/share/man"),
+  ("_infodir", "/usr/share/info"),
+  ("_rundir", "/run"),
+  ("_unitdir", "/usr/lib/systemd/system"),
+  ("_userunitdir", "/usr/lib/systemd/user"),
+  ("_sysusersdir", "/usr/lib/sysusers.d"),
+  ("_tmpfilesdir", "/usr/lib/tmpfiles.d"),
+];
+
+/// The main entry point for the `lint` command. Fails when any error was found.
+pub fn run(
+  config: &RevolveConfig,
+  package: &CargoPackage,
+  target_dir: &Path,
+  no_archive: bool,
+  format: LintFormat,
+) -> Result<()> {
+  let issues = lint(config, package, target_dir, no_archive);
+  let errors = issues.iter().filter(|issue| issue.severity == Severity::Error).count();
+  let warnings = issues.len() - errors;
+
+  match format {
+    LintFormat::Json => {
+      let report = LintReport {
+        package: &package.name,
+        version: package.version.to_string(),
+        errors,
+        warnings,
+        issues: &issues,
+      };
+      let json = serde_json::to_string_pretty(&report).context("Failed to serialize the report")?;
+      println!("{}", json);
+    }
+    LintFormat::Text => {
+      println!("Linting {} {}...", package.name, package.version);
+      for issue in &issues {
+        let severity = match issue.severity {
+          Severity::Error => "error",
+          Severity::Warning => "warning",
+        };
+        println!("{}[{}]: {}", severity, issue.check, issue.message);
+      }
+      if issues.is_empty() {
+        println!("No issues found.");
+      } else {
+        println!("\n{} error(s), {} warning(s)", errors, warnings);
+      }
+    }
+  }
+
+  if errors > 0 {
+    return Err(RevolveError::Lint {
+      package: package.name.to_string(),
+      errors,
+    });
+  }
+  Ok(())
+}
+
+fn error(check: &'static str, message: String) -> Issue {
+  Issue {
+    severity: Severity::Error,
+    check,
+    message,
+  }
+}
+
+fn warning(check: &'static str, message: String) -> Issue {
+  Issue {
+    severity: Severity::Warning,
+    check,
+    message,
+  }
+}
+
+/// Checks the configuration, renders the spec template without compiling anything, and checks
+/// the result. Problems that make the later checks impossible end the run early.
+pub fn lint(
+  config: &RevolveConfig,
+  package: &CargoPackage,
+  target_dir: &Path,
+  no_archive: bool,
+) -> Vec<Issue> {
+  let mut issues = Vec::new();
+  let manifest_dir = package.manifest_path.parent().unwrap().as_std_path();
+
+  let template_path = manifest_dir.join(&config.spec_template);
+  let template = match fs::read_to_string(&template_path) {
+    Ok(template) => Some(template),
+    Err(e) => {
+      issues.push(error(
+        "spec_template",
+        format!("Cannot read the spec template {}: {}", template_path.display(), e),
+      ));
+      None
+    }
+  };
+
+  let ResolvedConfig {
+    config: resolved,
+    scripts,
+    systemd,
+    sysusers,
+  } = match build::resolve_config(config, package, target_dir) {
+    Ok(resolved) => resolved,
+    Err(e) => {
+      issues.push(error("config", chain_message(&e)));
+      return issues;
+    }
+  };
+
+  let target_triple = config.target.as_deref();
+  let rpm_arch = match target_triple.map(arch::rpm_arch_for_target) {
+    None => arch::host_rpm_arch(),
+    Some(Ok(rpm_arch)) => rpm_arch,
+    Some(Err(e)) => {
+      issues.push(error("target", chain_message(&e)));
+      return issues;
+    }
+  };
+
+  // Assets with problems are reported once and left out of the remaining checks.
+  let mut checked = resolved.clone();
+  checked.assets = resolved
+    .assets
+    .as_deref()
+    .map(|assets| usable_assets(assets, &resolved, manifest_dir, target_dir, &mut issues));
+  for sub in checked.subpackages.iter_mut().flat_map(|subs| subs.values_mut()) {
+    if let Some(assets) = &sub.assets {
+      sub.assets = Some(usable_assets(assets, &resolved, manifest_dir, target_dir, &mut issues));
+    }
+  }
+
+  let ExpandedConfig {
+    config: expanded,
+    created_dirs,
+    subpackages,
+  } = match build::expand_config(&checked, package, target_dir, target_triple) {
+    Ok(expanded) => expanded,
+    Err(e) => {
+      issues.push(error("assets", chain_message(&e)));
+      return issues;
+    }
+  };
+
+  let Some(template) = template else {
+    return issues;
+  };
+  let plan = PackagePlan {
+    config: expanded,
+    package,
+    target_dir,
+    created_dirs,
+    subpackages,
+    // Binaries are not built for linting, so there is nothing to scan.
+    auto_requires: Vec::new(),
+    scripts,
+    systemd,
+    sysusers,
+    rpm_arch,
+    target_triple: target_triple.map(str::to_string),
+    source_date_epoch: None,
+    message_format: build::MessageFormat::Human,
+    backend: build::Backend::Rpmbuild,
+  };
+
+  let context = match build::template_context(&plan) {
+    Ok(context) => context.into_json(),
+    Err(e) => {
+      issues.push(error("render", chain_message(&e)));
+      return issues;
+    }
+  };
+  let references = template_references(&template);
+  let undefined = check_variables(&references, &context, &plan, &mut issues);
+
+  match build::render_spec_content(&plan) {
+    Ok(spec) => check_spec(&spec, &plan, no_archive, &mut issues),
+    // An undefined variable is the usual reason, and it has been reported already.
+    Err(_) if undefined => {}
+    Err(e) => issues.push(error("render", chain_message(&e))),
+  }
+  issues
+}
+
+/// Checks each asset on its own and returns the ones whose source can be expanded.
+fn usable_assets(
+  assets: &[Asset],
+  config: &RevolveConfig,
+  manifest_dir: &Path,
+  target_dir: &Path,
+  issues: &mut Vec<Issue>,
+) -> Vec<Asset> {
+  // Cross-compiled artifacts are looked up where the build will put them.
+  let located = match &config.target {
+    Some(triple) => build::retarget_assets(assets, triple, config.build_flags.as_deref()),
+    None => assets.to_vec(),
+  };
+  assets
+    .iter()
+    .zip(&located)
+    .filter(|(asset, located)| check_asset(asset, located, manifest_dir, target_dir, issues))
+    .map(|(asset, _)| asset.clone())
+    .collect()
+}
+
+/// Checks the `dest`, `mode`, `mkdir` and `source` of one configured asset. Returns `false`
+/// when its source is missing.
+fn check_asset(
+  asset: &Asset,
+  located: &Asset,
+  manifest_dir: &Path,
+  target_dir: &Path,
+  issues: &mut Vec<Issue>,
+) -> bool {
+  if !asset.dest.starts_with('/') {
+    issues.push(error(
+      "dest",
+      format!("Asset destination '{}' is not an absolute path.", asset.dest),
+    ));
+  }
+
+  if let Some(mode) = &asset.mode
+    && !((3..=4).contains(&mode.len()) && mode.chars().all(|c| ('0'..='7').contains(&c)))
+  {
+    issues.push(error(
+      "mode",
+      format!(
+        "Asset '{}' has mode '{}', which is not an octal permission like '0644'.",
+        asset.dest, mode
+      ),
+    ));
+  }
+
+  let is_directory_source = asset.source.ends_with('/') || asset.source.contains(GLOB_CHARS);
+  if !asset.mkdir && asset.kind != Some(AssetKind::Dir) {
+    // Directory and glob assets install below `dest`; single files next to it.
+    let dest = Path::new(&asset.dest);
+    let dir = if is_directory_source { Some(dest) } else { dest.parent() };
+    if let Some(dir) = dir.and_then(Path::to_str)
+      && !SYSTEM_DIRS.contains(&dir)
+      && !SYSTEM_DIRS.contains(&dir.trim_end_matches('/'))
+    {
+      issues.push(warning(
+        "mkdir",
+        format!(
+          "Asset '{}' sets `mkdir = false`, but {} is not a system directory, so no package \
+           will own it.",
+          asset.dest, dir
+        ),
+      ));
+    }
+  }
+
+  if !located.has_source() {
+    return true;
+  }
+  let source = &located.source;
+  let exists = if source.contains(GLOB_CHARS) {
+    build::resolve_asset_source(&build::glob_base(source), manifest_dir, target_dir).is_dir()
+  } else if source.ends_with('/') {
+    build::resolve_asset_source(source, manifest_dir, target_dir).is_dir()
+  } else {
+    build::resolve_asset_source(source, manifest_dir, target_dir).exists()
+  };
+  if exists {
+    return true;
+  }
+  if source.starts_with("target/") {
+    issues.push(warning(
+      "source",
+      format!(
+        "Asset source '{}' does not exist yet; it is expected to be created by the build.",
+        source
+      ),
+    ));
+  } else {
+    issues.push(error("source", format!("Asset source '{}' does not exist.", source)));
+  }
+  false
+}
+
+/// The `builder.*` and `pkg.*` variables used in the tags of a template, e.g.
+/// `builder.scripts.post`.
+fn template_references(template: &str) -> BTreeSet<String> {
+  let mut references = BTreeSet::new();
+  let mut rest = template;
+  while let Some(start) = [rest.find("{{"), rest.find("{%")].into_iter().flatten().min() {
+    let close = if rest[start..].starts_with("{{") { "}}" } else { "%}" };
+    let tag = &rest[start + 2..];
+    let end = tag.find(close).unwrap_or(tag.len());
+
+    let is_path_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.';
+    for token in tag[..end].split(|c: char| !is_path_char(c)) {
+      let token = token.trim_end_matches('.');
+      let root = token.split('.').next().unwrap_or_default();
+      if root == "builder" || root == "pkg" {
+        references.insert(token.to_string());
+      }
+    }
+    rest = &tag[end..];
+  }
+  references
+}
+
+/// Reports variables the template uses but the context does not define, and configured values
+/// the template never uses. Returns whether any variable is undefined.
+fn check_variables(
+  references: &BTreeSet<String>,
+  context: &Value,
+  plan: &PackagePlan,
+  issues: &mut Vec<Issue>,
+) -> bool {
+  let mut undefined = false;
+  for reference in references {
+    let mut fields = reference.split('.');
+    let root = fields.next().unwrap_or_default();
+    let known = if root == "builder" { BUILDER_FIELDS } else { PKG_FIELDS };
+    let mut value = context.get(root);
+    for (depth, field) in fields.enumerate() {
+      let missing = match value {
+        // Unset optional fields are left out of the context, so check the top level by name.
+        _ if depth == 0 => !known.contains(&field),
+        Some(Value::Object(map)) => !map.contains_key(field),
+        _ => false,
+      };
+      if missing {
+        issues.push(error(
+          "variables",
+          format!("The template uses `{}`, which is not defined.", reference),
+        ));
+        undefined = true;
+        break;
+      }
+      value = value.and_then(|value| value.get(field));
+    }
+  }
+
+  let uses = |variable: &str| {
+    references.iter().any(|reference| {
+      reference == variable
+        || reference.starts_with(&format!("{}.", variable))
+        || variable.starts_with(&format!("{}.", reference))
+    })
+  };
+
+  // Each configured setting and the `builder` field it reaches the template through.
+  let config = &plan.config;
+  fn configured<T>(list: &Option<Vec<T>>) -> bool {
+    list.as_ref().is_some_and(|list| !list.is_empty())
+  }
+  let settings = [
+    (configured(&config.assets), "assets", "assets"),
+    (configured(&config.requires), "requires", "requires"),
+    (configured(&config.provides), "provides", "provides"),
+    (configured(&config.conflicts), "conflicts", "conflicts"),
+    (configured(&config.obsoletes), "obsoletes", "obsoletes"),
+    (configured(&config.build_requires), "build_requires", "build_requires"),
+    (config.changelog.is_some(), "changelog", "changelog"),
+    (configured(&config.systemd_units), "systemd_units", "systemd"),
+    (configured(&config.users), "users", "sysusers"),
+    (!plan.subpackages.is_empty(), "subpackages", "subpackages"),
+  ];
+  let mut unused: Vec<(String, String)> = settings
+    .into_iter()
+    .filter(|(is_configured, ..)| *is_configured)
+    .map(|(_, setting, field)| (setting.to_string(), format!("builder.{}", field)))
+    .collect();
+  unused.extend(
+    plan
+      .scripts
+      .iter()
+      .map(|(name, _)| (format!("scripts.{}", name), format!("builder.scripts.{}", name))),
+  );
+  for (setting, variable) in unused.into_iter().filter(|(_, variable)| !uses(variable)) {
+    issues.push(warning(
+      "variables",
+      format!(
+        "`{}` is configured, but the template never uses `{}`, so it has no effect.",
+        setting, variable
+      ),
+    ));
+  }
+  if plan.created_dirs.as_ref().is_some_and(|dirs| !dirs.is_empty())
+    && !uses("builder.created_dirs")
+  {
+    issues.push(warning(
+      "variables",
+      "The template never uses `builder.created_dirs`, so the directories created for the \
+       assets are not owned by the package."
+        .to_string(),
+    ));
+  }
+  undefined
+}
+
+/// Checks the rendered spec: the required tags, `%setup` against the archive mode, and that
+/// every asset is listed in the `%files` section of its package.
+fn check_spec(spec: &str, plan: &PackagePlan, no_archive: bool, issues: &mut Vec<Issue>) {
+  for tag in ["Name", "Version", "Release", "Summary", "License"] {
+    let value = spec.lines().find_map(|line| {
+      let (key, value) = line.split_once(':')?;
+      key.trim().eq_ignore_ascii_case(tag).then(|| value.trim())
+    });
+    match value {
+      None => issues.push(error("tags", format!("The spec has no `{}:` tag.", tag))),
+      Some("") => issues.push(error("tags", format!("The `{}:` tag is empty.", tag))),
+      Some(_) => {}
+    }
+  }
+  if !spec.lines().any(|line| section_name(line) == Some("description")) {
+    issues.push(error("tags", "The spec has no `%description` section.".to_string()));
+  }
+
+  let unpacks = spec.lines().any(|line| {
+    let line = line.trim_start();
+    line.starts_with("%setup") || line.starts_with("%autosetup")
+  });
+  if no_archive && unpacks {
+    issues.push(error(
+      "setup",
+      "The spec uses `%setup`, but `--no-archive` builds have no source archive to unpack."
+        .to_string(),
+    ));
+  } else if !no_archive && !unpacks {
+    issues.push(warning(
+      "setup",
+      "The spec never unpacks the source archive with `%setup`; use `--no-archive` if it \
+       installs the files from `%{_sourcedir}`."
+        .to_string(),
+    ));
+  }
+
+  let sections = files_sections(spec, &plan.package.name);
+  let packages = std::iter::once((
+    plan.package.name.as_str(),
+    plan.config.assets.as_deref().unwrap_or_default(),
+  ))
+  .chain(plan.subpackages.iter().map(|sub| (sub.full_name.as_str(), sub.assets.as_slice())));
+  for (name, assets) in packages {
+    let Some(section) = sections.get(name) else {
+      if !assets.is_empty() {
+        issues.push(error("files", format!("The spec has no `%files` section for {}.", name)));
+      }
+      continue;
+    };
+    // Files listed with `-f` are only known once rpmbuild runs.
+    if section.has_file_list {
+      continue;
+    }
+    for asset in assets {
+      let dest = Path::new(&asset.dest);
+      let listed = section.entries.iter().any(|entry| {
+        entry.matcher.is_match(dest)
+          || (!entry.dir_only && dest.ancestors().skip(1).any(|dir| entry.matcher.is_match(dir)))
+      });
+      if !listed {
+        issues.push(error(
+          "files",
+          format!(
+            "Asset destination '{}' is not listed in the `%files` section of {}.",
+            asset.dest, name
+          ),
+        ));
+      }
+    }
+  }
+}
+
+/// The paths listed in one `%files` section.
+#[derive(Default)]
+struct FilesSection {
+  /// Set by `%files -f <list>`.
+  has_file_list: bool,
+  entries: Vec<FilesEntry>,
+}
+
+struct FilesEntry {
+  matcher: GlobMatcher,
+  /// Listed with `%dir`, so it does not include the files below it.
+  dir_only: bool,
+}
+
+/// The section a line starts, if any, e.g. `files` for `%files cli`.
+fn section_name(line: &str) -> Option<&str> {
+  let name = line.trim_start().strip_prefix('%')?;
+  let end = name.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(name.len());
+  let name = &name[..end];
+  SPEC_SECTIONS.contains(&name).then_some(name)
+}
+
+/// Collects the `%files` sections of a spec by full package name.
+fn files_sections(spec: &str, package_name: &str) -> HashMap<String, FilesSection> {
+  let mut sections: HashMap<String, FilesSection> = HashMap::new();
+  let mut current: Option<String> = None;
+
+  for line in spec.lines() {
+    if let Some(section) = section_name(line) {
+      current = None;
+      if section != "files" {
+        continue;
+      }
+      let mut name = package_name.to_string();
+      let mut has_file_list = false;
+      let mut args = line.split_whitespace().skip(1);
+      while let Some(arg) = args.next() {
+        match arg {
+          "-n" => name = args.next().unwrap_or_default().to_string(),
+          "-f" => {
+            args.next();
+            has_file_list = true;
+          }
+          _ if arg.starts_with('-') => {}
+          _ => name = format!("{}-{}", package_name, arg),
+        }
+      }
+      let section = sections.entry(name.clone()).or_default();
+      section.has_file_list |= has_file_list;
+      current = Some(name);
+      continue;
+    }
+
+    let Some(name) = &current else {
+      continue;
+    };
+    if let Some((path, dir_only)) = parse_files_entry(line)
+      && let Ok(glob) = GlobBuilder::new(&path).literal_separator(true).build()
+    {
+      let matcher = glob.compile_matcher();
+      sections.get_mut(name).unwrap().entries.push(FilesEntry { matcher, dir_only });
+    }
+  }
+  sections
+}
+
+/// The path of a `%files` line with its directives removed and path macros expanded, and
+/// whether it was listed with `%dir`.
+fn parse_files_entry(line: &str) -> Option<(String, bool)> {
+  let mut rest = line.trim();
+  let mut dir_only = false;
+  while let Some(directive) = rest.strip_prefix('%') {
+    let end = directive
+      .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+      .unwrap_or(directive.len());
+    let name = &directive[..end];
+    if name == "defattr" || name == "exclude" {
+      return None;
+    }
+    // Anything else, e.g. `%{_bindir}/...`, is the start of the path.
+    if !FILE_DIRECTIVES.contains(&name) {
+      break;
+    }
+    dir_only |= name == "dir";
+    rest = directive[end..].trim_start();
+    if rest.starts_with('(') {
+      rest = rest.find(')').map_or("", |close| &rest[close + 1..]).trim_start();
+    }
+  }
+
+  let path = expand_path_macros(rest.trim_matches('"'));
+  // Relative `%doc` and `%license` entries name files in the build directory.
+  if !path.starts_with('/') {
+    return None;
+  }
+  // A directory may be written with a trailing slash; it is matched against paths without one.
+  let trimmed = path.trim_end_matches('/');
+  Some((if trimmed.is_empty() { path } else { trimmed.to_string() }, dir_only))
+}
+
+/// Replaces the macros in a `%files` path with their usual values, or `*` for unknown ones.
+fn expand_path_macros(path: &str) -> String {
+  let mut expanded = String::new();
+  let mut rest = path;
+  while let Some(start) = rest.find('%') {
+    expanded.push_str(&rest[..start]);
+    let after = &rest[start + 1..];
+    let (name, len) = match after.strip_prefix('{') {
+      Some(inner) => match inner.find('}') {
+        Some(end) => (inner[..end].trim_start_matches(['?', '!']), end + 2),
+        None => (inner, after.len()),
+      },
+      None => {
+        let end = after
+          .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+          .unwrap_or(after.len());
+        (&after[..end], end)
+      }
+    };
+    match PATH_MACROS.iter().find(|(macro_name, _)| *macro_name == name) {
+      Some((_, value)) => expanded.push_str(value),
+      None => expanded.push('*'),
+    }
+    rest = &after[len..];
+  }
+  expanded.push_str(rest);
+  expanded
+}
@@ -1,2 +1,8 @@
+//! One module per subcommand.
+
 pub mod build;
-pub mod info;
\ No newline at end of file
+pub mod diff;
+pub mod extract;
+pub mod info;
+pub mod init;
+pub mod lint;
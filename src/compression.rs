@@ -0,0 +1,143 @@
+use crate::config::CompressionFormat;
+use crate::error::{Result, RevolveError};
+
+use std::fs::File;
+use std::io::{self, Write};
+
+use flate2::write::GzEncoder;
+
+impl CompressionFormat {
+  /// The file name extension of a tar archive compressed with this format.
+  pub fn archive_extension(self) -> &'static str {
+    match self {
+      CompressionFormat::Gzip => "tar.gz",
+      CompressionFormat::Xz => "tar.xz",
+      CompressionFormat::Bzip2 => "tar.bz2",
+      CompressionFormat::Zstd => "tar.zst",
+      CompressionFormat::None => "tar",
+    }
+  }
+
+  /// The range of levels the format accepts, or `None` for no compression.
+  fn levels(self) -> Option<(u32, u32)> {
+    match self {
+      CompressionFormat::Gzip | CompressionFormat::Xz => Some((0, 9)),
+      CompressionFormat::Bzip2 => Some((1, 9)),
+      CompressionFormat::Zstd => Some((1, 22)),
+      CompressionFormat::None => None,
+    }
+  }
+}
+
+/// Checks that `level` is valid for `format`. `setting` names the config key in errors.
+pub fn validate_level(setting: &str, format: CompressionFormat, level: Option<u32>) -> Result<()> {
+  let Some(level) = level else {
+    return Ok(());
+  };
+  match format.levels() {
+    None => Err(RevolveError::Config(format!(
+      "`{}` cannot be set without compression.",
+      setting
+    ))),
+    Some((min, max)) if level < min || level > max => Err(RevolveError::Config(format!(
+      "`{}` must be between {} and {} for {:?}, found {}.",
+      setting, min, max, format, level
+    ))),
+    Some(_) => Ok(()),
+  }
+}
+
+/// A writer compressing the source archive with the configured format.
+pub enum ArchiveEncoder {
+  Gzip(GzEncoder<File>),
+  Xz(liblzma::write::XzEncoder<File>),
+  Bzip2(bzip2::write::BzEncoder<File>),
+  Zstd(zstd::Encoder<'static, File>),
+  None(File),
+}
+
+impl ArchiveEncoder {
+  /// Wraps `file` in an encoder. Without a `level`, each format's own default is used.
+  pub fn new(file: File, format: CompressionFormat, level: Option<u32>) -> io::Result<Self> {
+    Ok(match format {
+      CompressionFormat::Gzip => {
+        let level = level.map_or_else(flate2::Compression::default, flate2::Compression::new);
+        ArchiveEncoder::Gzip(GzEncoder::new(file, level))
+      }
+      CompressionFormat::Xz => {
+        ArchiveEncoder::Xz(liblzma::write::XzEncoder::new(file, level.unwrap_or(6)))
+      }
+      CompressionFormat::Bzip2 => {
+        let level = level.map_or_else(bzip2::Compression::default, bzip2::Compression::new);
+        ArchiveEncoder::Bzip2(bzip2::write::BzEncoder::new(file, level))
+      }
+      CompressionFormat::Zstd => {
+        let level = level.map_or(zstd::DEFAULT_COMPRESSION_LEVEL, |level| level as i32);
+        ArchiveEncoder::Zstd(zstd::Encoder::new(file, level)?)
+      }
+      CompressionFormat::None => ArchiveEncoder::None(file),
+    })
+  }
+
+  /// Writes the end of the compressed stream.
+  pub fn finish(self) -> io::Result<()> {
+    match self {
+      ArchiveEncoder::Gzip(encoder) => encoder.finish().map(drop),
+      ArchiveEncoder::Xz(encoder) => encoder.finish().map(drop),
+      ArchiveEncoder::Bzip2(encoder) => encoder.finish().map(drop),
+      ArchiveEncoder::Zstd(encoder) => encoder.finish().map(drop),
+      ArchiveEncoder::None(mut file) => file.flush(),
+    }
+  }
+}
+
+impl Write for ArchiveEncoder {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    match self {
+      ArchiveEncoder::Gzip(encoder) => encoder.write(buf),
+      ArchiveEncoder::Xz(encoder) => encoder.write(buf),
+      ArchiveEncoder::Bzip2(encoder) => encoder.write(buf),
+      ArchiveEncoder::Zstd(encoder) => encoder.write(buf),
+      ArchiveEncoder::None(file) => file.write(buf),
+    }
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    match self {
+      ArchiveEncoder::Gzip(encoder) => encoder.flush(),
+      ArchiveEncoder::Xz(encoder) => encoder.flush(),
+      ArchiveEncoder::Bzip2(encoder) => encoder.flush(),
+      ArchiveEncoder::Zstd(encoder) => encoder.flush(),
+      ArchiveEncoder::None(file) => file.flush(),
+    }
+  }
+}
+
+/// The payload compression for the native backend. Without a `level`, rpm's defaults are used.
+pub fn rpm_payload(format: CompressionFormat, level: Option<u32>) -> rpm::CompressionWithLevel {
+  use rpm::CompressionWithLevel as Payload;
+  match (format, level) {
+    (CompressionFormat::Gzip, Some(level)) => Payload::Gzip(level),
+    (CompressionFormat::Xz, Some(level)) => Payload::Xz(level),
+    (CompressionFormat::Bzip2, Some(level)) => Payload::Bzip2(level),
+    (CompressionFormat::Zstd, Some(level)) => Payload::Zstd(level as i32),
+    (CompressionFormat::Gzip, None) => rpm::CompressionType::Gzip.into(),
+    (CompressionFormat::Xz, None) => rpm::CompressionType::Xz.into(),
+    (CompressionFormat::Bzip2, None) => rpm::CompressionType::Bzip2.into(),
+    (CompressionFormat::Zstd, None) => rpm::CompressionType::Zstd.into(),
+    (CompressionFormat::None, _) => Payload::None,
+  }
+}
+
+/// The value of rpmbuild's `_binary_payload` macro, e.g. `w19.zstdio`. Without a `level`, the
+/// levels rpmbuild itself uses for each format are filled in.
+pub fn binary_payload_macro(format: CompressionFormat, level: Option<u32>) -> String {
+  let (default_level, io) = match format {
+    CompressionFormat::Gzip => (9, "gzdio"),
+    CompressionFormat::Xz => (2, "xzdio"),
+    CompressionFormat::Bzip2 => (9, "bzdio"),
+    CompressionFormat::Zstd => (19, "zstdio"),
+    CompressionFormat::None => return "w.ufdio".to_string(),
+  };
+  format!("w{}.{}", level.unwrap_or(default_level), io)
+}
@@ -1,13 +1,70 @@
+use crate::error::{Result, RevolveError};
+
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
 
 /// Represents a single asset to be packaged, from the `assets` array.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Asset {
+  /// Not needed for `ghost` and `dir` assets, which have no content, or for symlinks.
+  #[serde(default)]
   pub source: String,
   pub dest: String,
   pub mode: Option<String>,
+  /// The owning user; `root` when unset.
+  pub user: Option<String>,
+  /// The owning group; `root` when unset.
+  pub group: Option<String>,
   #[serde(default = "default_mkdir")]
   pub mkdir: bool,
+  pub kind: Option<AssetKind>,
+  /// Makes the asset a symlink at `dest` pointing to this path instead of a copy of `source`.
+  pub symlink_target: Option<String>,
+  /// For directory assets: package symlinks found in the source directory as symlinks instead
+  /// of copying the files they point to.
+  #[serde(default)]
+  pub preserve_symlinks: bool,
+  /// For directory and glob assets: patterns of files to leave out, relative to the directory
+  /// (e.g. `*.bak` or `local/**`).
+  pub exclude: Option<Vec<String>>,
+  /// For directory and glob assets: skip the files ignored by `.gitignore`.
+  #[serde(default)]
+  pub gitignore: bool,
+  /// Where the source is stored in the artifact archive, relative to its root directory.
+  /// Filled in when the assets are expanded; it cannot be configured.
+  #[serde(skip_deserializing)]
+  pub archive_path: Option<String>,
+  /// The absolute path of the source on disk, with `target/` resolved to the Cargo target
+  /// directory. Filled in when the assets are expanded; it cannot be configured.
+  #[serde(skip_deserializing)]
+  pub source_path: Option<String>,
+}
+
+impl Asset {
+  /// Whether the asset is backed by a file on disk that has to be packaged.
+  pub fn has_source(&self) -> bool {
+    !self.source.is_empty() && self.kind != Some(AssetKind::Dir) && self.symlink_target.is_none()
+  }
+}
+
+/// How an asset is listed in `%files`. Assets without a kind are plain files.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetKind {
+  /// `%config`: a modified file is saved as `.rpmsave` and replaced on upgrade.
+  Config,
+  /// `%config(noreplace)`: a modified file is kept and the new one written as `.rpmnew`.
+  ConfigNoreplace,
+  Doc,
+  License,
+  /// `%ghost`: owned by the package but not shipped in it, e.g. a log file.
+  Ghost,
+  /// `%dir`: an empty directory owned by the package.
+  Dir,
 }
 
 // This function provides the default value for `mkdir` to serde.
@@ -23,15 +80,289 @@ pub enum BuildCommand {
   Sequence(Vec<String>),
 }
 
+/// The version comparison of a dependency.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyOp {
+  #[serde(rename = "=", alias = "==")]
+  Eq,
+  #[serde(rename = "<")]
+  Lt,
+  #[serde(rename = "<=")]
+  Le,
+  #[serde(rename = ">")]
+  Gt,
+  #[serde(rename = ">=")]
+  Ge,
+}
+
+impl DependencyOp {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      DependencyOp::Eq => "=",
+      DependencyOp::Lt => "<",
+      DependencyOp::Le => "<=",
+      DependencyOp::Gt => ">",
+      DependencyOp::Ge => ">=",
+    }
+  }
+}
+
+impl FromStr for DependencyOp {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "=" | "==" => Ok(DependencyOp::Eq),
+      "<" => Ok(DependencyOp::Lt),
+      "<=" => Ok(DependencyOp::Le),
+      ">" => Ok(DependencyOp::Gt),
+      ">=" => Ok(DependencyOp::Ge),
+      _ => Err(format!("unknown version operator '{}'", s)),
+    }
+  }
+}
+
+/// A package relationship such as `Requires: openssl-libs >= 3.0`.
+///
+/// Written either as a table, `{ name = "openssl-libs", op = ">=", version = "3.0" }`,
+/// or as the equivalent string, `"openssl-libs >= 3.0"`. Templates additionally see a `spec`
+/// field holding the string form.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(try_from = "RawDependency", into = "DependencyFields")]
+pub struct Dependency {
+  pub name: String,
+  pub op: Option<DependencyOp>,
+  pub version: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawDependency {
+  Text(String),
+  Table {
+    name: String,
+    op: Option<DependencyOp>,
+    version: Option<String>,
+  },
+}
+
+#[derive(Serialize)]
+struct DependencyFields {
+  name: String,
+  op: Option<DependencyOp>,
+  version: Option<String>,
+  spec: String,
+}
+
+impl From<Dependency> for DependencyFields {
+  fn from(dependency: Dependency) -> Self {
+    let spec = dependency.to_string();
+    DependencyFields {
+      name: dependency.name,
+      op: dependency.op,
+      version: dependency.version,
+      spec,
+    }
+  }
+}
+
+impl TryFrom<RawDependency> for Dependency {
+  type Error = String;
+
+  fn try_from(raw: RawDependency) -> Result<Self, Self::Error> {
+    let (name, op, version) = match raw {
+      RawDependency::Table { name, op, version } => (name, op, version),
+      RawDependency::Text(text) => {
+        let parts: Vec<&str> = text.split_whitespace().collect();
+        match parts.as_slice() {
+          [name] => (name.to_string(), None, None),
+          [name, op, version] => (name.to_string(), Some(op.parse()?), Some(version.to_string())),
+          _ => {
+            return Err(format!(
+              "invalid dependency '{}', expected 'name' or 'name <op> version'",
+              text
+            ));
+          }
+        }
+      }
+    };
+
+    if name.is_empty() || name.contains(char::is_whitespace) {
+      return Err(format!("invalid dependency name '{}'", name));
+    }
+    if op.is_some() != version.is_some() {
+      return Err(format!(
+        "dependency '{}' must set both `op` and `version`, or neither",
+        name
+      ));
+    }
+    Ok(Dependency { name, op, version })
+  }
+}
+
+impl fmt::Display for Dependency {
+  /// Formats the dependency the way it is written after `Requires:` in a .spec file.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match (self.op, &self.version) {
+      (Some(op), Some(version)) => write!(f, "{} {} {}", self.name, op.as_str(), version),
+      _ => f.write_str(&self.name),
+    }
+  }
+}
+
+/// Represents a `[package.metadata.revolve.subpackages.<name>]` table.
+/// The resulting RPM is named `<package>-<name>`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Subpackage {
+  pub summary: Option<String>,
+  pub description: Option<String>,
+  pub requires: Option<Vec<Dependency>>,
+  pub assets: Option<Vec<Asset>>,
+}
+
+/// Represents the `[package.metadata.revolve.auto_requires]` table.
+/// Both lists take glob patterns matched against sonames, e.g. `libssl.so.*`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct AutoRequiresConfig {
+  #[serde(default = "default_enabled")]
+  pub enabled: bool,
+  /// When non-empty, only matching sonames are kept.
+  #[serde(default)]
+  pub include: Vec<String>,
+  #[serde(default)]
+  pub exclude: Vec<String>,
+}
+
+fn default_enabled() -> bool {
+  true
+}
+
+impl Default for AutoRequiresConfig {
+  fn default() -> Self {
+    AutoRequiresConfig {
+      enabled: true,
+      include: Vec::new(),
+      exclude: Vec::new(),
+    }
+  }
+}
+
+/// Represents the `scripts` table. Each key is the path of a shell file, relative to the
+/// manifest, whose contents become the scriptlet of the same name.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Scripts {
+  pub pre: Option<String>,
+  pub post: Option<String>,
+  pub preun: Option<String>,
+  pub postun: Option<String>,
+  pub pretrans: Option<String>,
+  pub posttrans: Option<String>,
+}
+
+/// An entry of the `users` array: a system account created from a generated sysusers.d file.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct User {
+  pub name: String,
+  /// The primary group. Defaults to a group named after the user.
+  pub group: Option<String>,
+  pub home: Option<String>,
+  pub shell: Option<String>,
+  /// sysusers.d only creates system accounts, so this must stay `true`.
+  #[serde(default = "default_system")]
+  pub system: bool,
+}
+
+fn default_system() -> bool {
+  true
+}
+
+/// A compression format for the source archive or the RPM payload.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionFormat {
+  Gzip,
+  Xz,
+  Bzip2,
+  Zstd,
+  None,
+}
+
 /// Represents the `[package.metadata.revolve]` table in Cargo.toml.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 pub struct RevolveConfig {
   pub spec_template: String,
   pub output_dir: Option<String>,
+  /// The RPM release used by the native backend. Defaults to "1".
+  pub release: Option<String>,
   pub changelog: Option<String>,
   pub build_flags: Option<Vec<String>>,
+  /// A Rust target triple to cross-compile for, e.g. `aarch64-unknown-linux-gnu`.
+  pub target: Option<String>,
   pub build_command: Option<BuildCommand>,
   pub assets: Option<Vec<Asset>>,
+  pub subpackages: Option<BTreeMap<String, Subpackage>>,
+  pub requires: Option<Vec<Dependency>>,
+  pub provides: Option<Vec<Dependency>>,
+  pub conflicts: Option<Vec<Dependency>>,
+  pub obsoletes: Option<Vec<Dependency>>,
+  /// Only used by the `rpmbuild` backend; binary RPMs do not record build dependencies.
+  pub build_requires: Option<Vec<Dependency>>,
+  /// Controls the shared library requirements detected from the packaged binaries.
+  pub auto_requires: Option<AutoRequiresConfig>,
+  pub scripts: Option<Scripts>,
+  /// Unit files installed into `%{_unitdir}` with generated `%systemd_*` scriptlets.
+  pub systemd_units: Option<Vec<String>>,
+  /// System accounts created at install time through a generated sysusers.d file.
+  pub users: Option<Vec<User>>,
+  /// How the source archive is compressed. Defaults to gzip.
+  pub archive_compression: Option<CompressionFormat>,
+  pub archive_compression_level: Option<u32>,
+  /// How the files inside the RPM are compressed. Defaults to the backend's own choice.
+  pub payload_compression: Option<CompressionFormat>,
+  pub payload_compression_level: Option<u32>,
   pub verify_license: Option<String>,
   pub verify_summary: Option<String>,
-}
\ No newline at end of file
+}
+
+impl RevolveConfig {
+  /// Reads the `[package.metadata.revolve]` table of the `Cargo.toml` at `manifest_path`.
+  pub fn from_manifest(manifest_path: &Path) -> Result<Self> {
+    let load_error = |source: Box<dyn std::error::Error + Send + Sync>| {
+      RevolveError::ManifestLoad { path: manifest_path.to_path_buf(), source }
+    };
+    let manifest_content = fs::read_to_string(manifest_path).map_err(|e| load_error(e.into()))?;
+    let manifest: Manifest = toml::from_str(&manifest_content).map_err(|e| load_error(e.into()))?;
+
+    manifest
+      .package
+      .metadata
+      .and_then(|m| m.revolve_config)
+      .ok_or_else(|| {
+        RevolveError::Config(format!(
+          "Missing `[package.metadata.revolve]` table in {}",
+          manifest_path.display()
+        ))
+      })
+  }
+}
+
+// This struct represents the `[package.metadata]` table
+#[derive(Deserialize, Debug)]
+struct MetadataToml {
+  #[serde(rename = "revolve")]
+  revolve_config: Option<RevolveConfig>,
+}
+
+// This struct represents the `[package]` table
+#[derive(Deserialize, Debug)]
+struct PackageToml {
+  metadata: Option<MetadataToml>,
+}
+
+// It represents the entire Cargo.toml file, which has a `[package]` table.
+#[derive(Deserialize, Debug)]
+struct Manifest {
+  package: PackageToml,
+}
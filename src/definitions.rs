@@ -1,6 +1,8 @@
 use serde::Serialize;
 
-use crate::config::Asset;
+use crate::config::{Asset, Dependency, User};
+
+use std::path::PathBuf;
 
 /// Data from the `[package]` section of Cargo.toml, passed to the template.
 #[derive(Serialize)]
@@ -17,6 +19,11 @@ pub struct BuilderContext<'a> {
   pub spec_template: &'a str,
   
   pub archive_root_dir: &'a str,
+  /// The file name of the source archive, e.g. for `Source0:`.
+  pub archive_filename: &'a str,
+
+  /// The RPM architecture being built, e.g. `x86_64` or `aarch64`. Useful for `BuildArch`.
+  pub rpm_arch: &'a str,
   
   #[serde(skip_serializing_if = "Option::is_none")]
   pub changelog: Option<&'a str>,
@@ -28,7 +35,130 @@ pub struct BuilderContext<'a> {
   pub build_flags: Option<&'a Vec<String>>,
   
   #[serde(skip_serializing_if = "Option::is_none")]
-  pub created_dirs: Option<Vec<String>>,
+  pub created_dirs: Option<&'a [String]>,
+
+  /// Always present (possibly empty) so templates can loop without a guard.
+  pub subpackages: &'a [SubpackageContext],
+
+  /// Declared dependencies; each has `name`, `op`, `version` and the combined `spec` string.
+  pub requires: &'a [Dependency],
+  pub provides: &'a [Dependency],
+  pub conflicts: &'a [Dependency],
+  pub obsoletes: &'a [Dependency],
+  pub build_requires: &'a [Dependency],
+
+  /// Shared libraries the packaged binaries link against, e.g. `libssl.so.3()(64bit)`.
+  pub auto_requires: &'a [Dependency],
+
+  /// Always present; scriptlets that are not configured are `null`.
+  pub scripts: &'a ScriptsContext,
+
+  /// The units from `systemd_units` and their `%systemd_*` scriptlet lines.
+  pub systemd: &'a SystemdContext,
+
+  /// The accounts from `users` and the generated sysusers.d file that creates them.
+  pub sysusers: &'a SysusersContext,
+}
+
+/// The fields of `pkg` available to templates.
+pub const PKG_FIELDS: &[&str] = &["name", "version", "description", "license"];
+
+/// The fields of `builder` available to templates, including the ones left out of the context
+/// when they are not configured.
+pub const BUILDER_FIELDS: &[&str] = &[
+  "spec_template",
+  "archive_root_dir",
+  "archive_filename",
+  "rpm_arch",
+  "changelog",
+  "assets",
+  "build_flags",
+  "created_dirs",
+  "subpackages",
+  "requires",
+  "provides",
+  "conflicts",
+  "obsoletes",
+  "build_requires",
+  "auto_requires",
+  "scripts",
+  "systemd",
+  "sysusers",
+];
+
+/// The configured system accounts, passed to the template as `builder.sysusers`.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SysusersContext {
+  pub users: Vec<User>,
+  /// Where the generated file is installed, e.g. `/usr/lib/sysusers.d/my-app.conf`.
+  /// `null` when no users are configured.
+  pub file: Option<String>,
+  /// `%sysusers_create_compat <generated file>` for the `%pre` scriptlet; empty when no users
+  /// are configured.
+  pub pre: String,
+  /// The asset source of the generated file.
+  pub source: String,
+  #[serde(skip)]
+  pub source_path: PathBuf,
+  #[serde(skip)]
+  pub content: String,
+}
+
+/// The configured systemd units, passed to the template as `builder.systemd`.
+/// The scriptlet lines are empty strings when no units are configured.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SystemdContext {
+  /// Unit file names, e.g. `my-app.service`.
+  pub units: Vec<String>,
+  /// `%systemd_post <units>`
+  pub post: String,
+  /// `%systemd_preun <units>`
+  pub preun: String,
+  /// `%systemd_postun_with_restart <units>`
+  pub postun: String,
+}
+
+/// The contents of the files named in the `scripts` table, passed to the template as
+/// `builder.scripts`.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ScriptsContext {
+  pub pre: Option<String>,
+  pub post: Option<String>,
+  pub preun: Option<String>,
+  pub postun: Option<String>,
+  pub pretrans: Option<String>,
+  pub posttrans: Option<String>,
+}
+
+impl ScriptsContext {
+  /// The configured scriptlets paired with their `.spec` section name, e.g. `("post", "...")`.
+  pub fn iter(&self) -> impl Iterator<Item = (&'static str, &str)> {
+    [
+      ("pre", &self.pre),
+      ("post", &self.post),
+      ("preun", &self.preun),
+      ("postun", &self.postun),
+      ("pretrans", &self.pretrans),
+      ("posttrans", &self.posttrans),
+    ]
+    .into_iter()
+    .filter_map(|(name, content)| Some((name, content.as_deref()?)))
+  }
+}
+
+/// A subpackage with its assets expanded, passed to the template as `builder.subpackages`.
+#[derive(Serialize, Debug, Clone)]
+pub struct SubpackageContext {
+  /// The short name used with `%package`, `%description` and `%files`, e.g. `cli`.
+  pub name: String,
+  /// The full RPM name, e.g. `my-app-cli`.
+  pub full_name: String,
+  pub summary: Option<String>,
+  pub description: Option<String>,
+  pub requires: Vec<Dependency>,
+  pub auto_requires: Vec<Dependency>,
+  pub assets: Vec<Asset>,
+  pub created_dirs: Vec<String>,
 }
 
 /// The top-level context object passed to the Tera templating engine.
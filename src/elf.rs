@@ -0,0 +1,108 @@
+use crate::commands::build::resolve_asset_source;
+use crate::config::{Asset, AutoRequiresConfig, Dependency};
+use crate::error::{Result, RevolveError};
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use goblin::elf::Elf;
+use goblin::elf::note::NT_GNU_BUILD_ID;
+
+/// Suggests `Requires:` entries for the shared libraries the packaged binaries link against.
+///
+/// Every asset that is executable (by `mode`) or built into `target/` is checked; ELF files
+/// contribute their `DT_NEEDED` sonames in rpm's own notation, e.g. `libssl.so.3()(64bit)`.
+/// Libraries shipped by the package itself and sonames filtered out by the configuration are
+/// left out. Sources that do not exist yet (e.g. on a dry run) are skipped.
+pub fn auto_requires(
+  assets: &[Asset],
+  config: &AutoRequiresConfig,
+  project_dir: &Path,
+  target_dir: &Path,
+) -> Result<Vec<Dependency>> {
+  if !config.enabled {
+    return Ok(Vec::new());
+  }
+  let include = build_globset(&config.include)?;
+  let exclude = build_globset(&config.exclude)?;
+
+  let shipped: BTreeSet<&str> = assets
+    .iter()
+    .filter_map(|asset| Path::new(&asset.dest).file_name()?.to_str())
+    .collect();
+
+  let mut requires = BTreeSet::new();
+  for asset in assets.iter().filter(|asset| is_binary_asset(asset)) {
+    let source_path = resolve_asset_source(&asset.source, project_dir, target_dir);
+    let Ok(bytes) = fs::read(&source_path) else {
+      log::debug!("Skipping auto-requires for missing file {}", source_path.display());
+      continue;
+    };
+    if !is_elf(&bytes) {
+      continue;
+    }
+    let elf = Elf::parse(&bytes)
+      .with_context(|| format!("Failed to parse ELF file {}", source_path.display()))?;
+    let suffix = if elf.is_64 { "()(64bit)" } else { "" };
+
+    for soname in elf.libraries {
+      if shipped.contains(soname) || exclude.is_match(soname) {
+        continue;
+      }
+      if !config.include.is_empty() && !include.is_match(soname) {
+        continue;
+      }
+      log::debug!("{} needs {}", asset.source, soname);
+      requires.insert(format!("{}{}", soname, suffix));
+    }
+  }
+
+  Ok(
+    requires
+      .into_iter()
+      .map(|name| Dependency { name, op: None, version: None })
+      .collect(),
+  )
+}
+
+/// Whether `bytes` are the contents of an ELF file.
+pub fn is_elf(bytes: &[u8]) -> bool {
+  bytes.starts_with(b"\x7fELF")
+}
+
+/// The GNU build ID of an ELF file, which survives stripping. `None` for other files and for
+/// binaries linked without one.
+pub fn build_id(bytes: &[u8]) -> Option<Vec<u8>> {
+  if !is_elf(bytes) {
+    return None;
+  }
+  let elf = Elf::parse(bytes).ok()?;
+  elf
+    .iter_note_sections(bytes, Some(".note.gnu.build-id"))?
+    .filter_map(|note| note.ok())
+    .find(|note| note.n_type == NT_GNU_BUILD_ID && note.name == "GNU")
+    .map(|note| note.desc.to_vec())
+}
+
+/// Binaries are the assets marked executable or produced by the build in `target/`.
+fn is_binary_asset(asset: &Asset) -> bool {
+  let executable = asset
+    .mode
+    .as_deref()
+    .and_then(|mode| u32::from_str_radix(mode, 8).ok())
+    .is_some_and(|mode| mode & 0o111 != 0);
+  asset.has_source() && (executable || asset.source.starts_with("target/"))
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet> {
+  let mut builder = GlobSetBuilder::new();
+  for pattern in patterns {
+    builder.add(Glob::new(pattern).map_err(|e| {
+      RevolveError::Config(format!("Invalid soname pattern '{}': {}", pattern, e))
+    })?);
+  }
+  Ok(builder.build().context("Failed to compile the soname patterns")?)
+}
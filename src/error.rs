@@ -1,2 +1,137 @@
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+
+use thiserror::Error;
+
 /// A specialized `Result` type for this application's operations.
-pub type Result<T> = anyhow::Result<T>;
\ No newline at end of file
+pub type Result<T, E = RevolveError> = std::result::Result<T, E>;
+
+/// Everything that can make a command fail.
+///
+/// Each kind of failure has its own process exit code, see [`RevolveError::exit_code`], so
+/// scripts can tell a broken configuration apart from a failed build or a bad package.
+#[derive(Debug, Error)]
+pub enum RevolveError {
+  /// `Cargo.toml` could not be read or parsed.
+  #[error("Failed to load manifest file at {}", path.display())]
+  ManifestLoad {
+    path: PathBuf,
+    #[source]
+    source: Box<dyn std::error::Error + Send + Sync>,
+  },
+  /// The `[package.metadata.revolve]` table is missing or invalid, or the command line asks for
+  /// something it does not allow.
+  #[error("{0}")]
+  Config(String),
+  /// The .spec template could not be loaded or rendered.
+  #[error("Failed to render spec template {}", path.display())]
+  Template {
+    path: PathBuf,
+    #[source]
+    source: tera::Error,
+  },
+  /// Two assets would be installed at the same path. `first` and `second` describe where each
+  /// one comes from, e.g. `source 'config/'`.
+  #[error(
+    "Duplicate asset destination found: '{dest}'.\n  - Provided by {first}\n  - Also provided by {second}"
+  )]
+  DuplicateDestination {
+    dest: String,
+    first: String,
+    second: String,
+  },
+  /// An asset source ending with `/` is not a directory.
+  #[error(
+    "Asset source '{0}' is marked as a directory (ends with '/') but is not a directory on disk."
+  )]
+  NotADirectory(String),
+  /// An asset source does not exist when it is packaged.
+  #[error(
+    "Asset source file not found: {}. Please run 'cargo build' first or ensure the path is correct.",
+    .0.display()
+  )]
+  MissingAssetSource(PathBuf),
+  /// Any other problem with the configured assets.
+  #[error("{0}")]
+  Asset(String),
+  /// The source archive could not be written.
+  #[error("Failed to create the source archive {}", path.display())]
+  Archive {
+    path: PathBuf,
+    #[source]
+    source: Box<dyn std::error::Error + Send + Sync>,
+  },
+  /// `cargo build` or a custom `build_command` exited unsuccessfully.
+  #[error("Build command `{command}` failed with {status}")]
+  BuildCommand { command: String, status: ExitStatus },
+  /// `rpmbuild` is not installed.
+  #[error("'rpmbuild' command not found. Please ensure it is installed and in your system's PATH.")]
+  RpmbuildNotFound(#[source] which::Error),
+  /// `rpmbuild` exited unsuccessfully.
+  #[error("'rpmbuild' failed with {0}")]
+  Rpmbuild(ExitStatus),
+  /// The built RPM does not match the configuration.
+  #[error("{0}")]
+  Verification(String),
+  /// `lint` found errors.
+  #[error("Lint found {errors} error(s) in {package}.")]
+  Lint { package: String, errors: usize },
+  /// `diff --fail-on` found a difference it does not allow.
+  #[error("{0}")]
+  Diff(String),
+  /// Some packages of a workspace build failed. `exit_code` is shared by all the failures, or 1
+  /// when they failed in different ways.
+  #[error("{failed} of {total} package(s) failed to build.")]
+  Packages {
+    failed: usize,
+    total: usize,
+    exit_code: u8,
+  },
+  #[error(transparent)]
+  Io(#[from] io::Error),
+  #[error(transparent)]
+  Rpm(#[from] rpm::Error),
+  /// Everything else, usually an I/O error with some context attached.
+  #[error(transparent)]
+  Other(#[from] anyhow::Error),
+}
+
+impl RevolveError {
+  /// The process exit code for this error. 2 is left to clap for command-line usage errors.
+  pub fn exit_code(&self) -> u8 {
+    match self {
+      RevolveError::ManifestLoad { .. } | RevolveError::Config(_) => 3,
+      RevolveError::Template { .. } => 4,
+      RevolveError::DuplicateDestination { .. }
+      | RevolveError::NotADirectory(_)
+      | RevolveError::MissingAssetSource(_)
+      | RevolveError::Asset(_) => 5,
+      RevolveError::Archive { .. } => 6,
+      RevolveError::BuildCommand { .. } => 7,
+      RevolveError::RpmbuildNotFound(_) | RevolveError::Rpmbuild(_) => 8,
+      RevolveError::Verification(_) => 9,
+      RevolveError::Lint { .. } => 10,
+      RevolveError::Diff(_) => 11,
+      RevolveError::Packages { exit_code, .. } => *exit_code,
+      RevolveError::Io(_) | RevolveError::Rpm(_) => 1,
+      // A typed error may have been given more context on its way up.
+      RevolveError::Other(error) => error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<RevolveError>())
+        .map_or(1, RevolveError::exit_code),
+    }
+  }
+}
+
+/// Formats `error` and its causes on one line, separated by colons.
+pub fn chain_message(error: &dyn std::error::Error) -> String {
+  let mut message = error.to_string();
+  let mut source = error.source();
+  while let Some(cause) = source {
+    message.push_str(": ");
+    message.push_str(&cause.to_string());
+    source = cause.source();
+  }
+  message
+}
@@ -0,0 +1,67 @@
+//! Build RPM packages for Cargo projects from a `.spec` template.
+//!
+//! This crate is the library behind the `cargo revolve` subcommand. Release tooling and `xtask`
+//! binaries can use it to run the same pipeline without shelling out:
+//!
+//! ```no_run
+//! use cargo_revolve::cargo_metadata::MetadataCommand;
+//! use cargo_revolve::{BuildOptions, RevolveConfig};
+//!
+//! # fn main() -> cargo_revolve::Result<()> {
+//! let metadata = MetadataCommand::new().exec().map_err(anyhow::Error::from)?;
+//! let package = metadata.root_package().expect("not a workspace root");
+//! let config = RevolveConfig::from_manifest(package.manifest_path.as_std_path())?;
+//!
+//! let target_dir = metadata.target_directory.as_std_path();
+//! let options = BuildOptions::new().verify(true);
+//! let report = cargo_revolve::build(&config, package, target_dir, &options)?;
+//! for artifact in &report.artifacts {
+//!   println!("Built {} ({})", artifact.path.display(), artifact.sha256);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`build_workspace`] builds a selection of workspace members the way `cargo revolve build`
+//! does, and keeps the reports of the packages that failed.
+//!
+//! The individual steps are available too: [`PackagePlan::new`] resolves a configuration
+//! without compiling anything, [`expand_assets`] expands directory and glob assets into one
+//! asset per file, [`render_spec`] renders its .spec file,
+//! [`create_artifact_archive`] writes its source archive and [`verify_package`] checks a built
+//! RPM against it. [`RpmInfo`] reads the header of any RPM file and
+//! [`extract_package`] unpacks its payload.
+
+mod arch;
+pub(crate) mod commands;
+mod compression;
+pub mod config;
+mod definitions;
+mod elf;
+pub mod error;
+mod native;
+pub mod report;
+mod systemd;
+mod sysusers;
+
+pub use cargo_metadata;
+
+pub use commands::build::{
+  Backend, BuildOptions, MessageFormat, PackagePlan, PackageSelection, WorkspaceBuild,
+  build_package, build_workspace, create_artifact_archive, expand_assets, render_spec,
+  render_spec_content, run as build, select_packages, verify_package,
+};
+pub use commands::extract::extract_package;
+pub use commands::info::RpmInfo;
+pub use config::RevolveConfig;
+pub use report::BuildReport;
+pub use error::{Result, RevolveError};
+
+/// The subcommands behind the `cargo-revolve` binary. Not part of the library API.
+#[doc(hidden)]
+pub mod cli {
+  pub use crate::commands::diff::{DiffCheck, DiffFormat};
+  pub use crate::commands::info::{InfoFormat, InfoQuery};
+  pub use crate::commands::lint::LintFormat;
+  pub use crate::commands::{diff, extract, info, init, lint};
+}
@@ -1,15 +1,13 @@
-use crate::config::RevolveConfig;
-use crate::error::Result;
-use anyhow::{anyhow, Context};
+use cargo_revolve::cli::{self, DiffCheck, DiffFormat, InfoFormat, InfoQuery, LintFormat};
+use cargo_revolve::report::write_reports;
+use cargo_revolve::{
+  Backend, BuildOptions, MessageFormat, PackageSelection, Result, RevolveConfig, select_packages,
+};
+use anyhow::Context;
+use cargo_metadata::{Metadata, Package as CargoPackage};
 use clap::{Parser, Subcommand};
-use std::{env, fs};
 use std::path::PathBuf;
-
-// Declare all our new modules
-mod commands;
-mod config;
-mod definitions;
-mod error;
+use std::process::ExitCode;
 
 // =================================================================================================
 // Command-Line Interface Definition
@@ -61,44 +59,108 @@ enum Commands {
     /// After building, verify the RPM contents against the Cargo.toml configuration.
     #[arg(long)]
     verify: bool,
+
+    /// The tool used to assemble the binary RPM. `native` writes it directly
+    /// and does not require `rpmbuild` to be installed.
+    #[arg(long, value_enum, default_value_t = Backend::Rpmbuild)]
+    backend: Backend,
+
+    /// Build every workspace member that has a `[package.metadata.revolve]` table.
+    #[arg(long)]
+    workspace: bool,
+
+    /// Build only the named package(s). May be given more than once.
+    #[arg(short = 'p', long = "package", value_name = "NAME")]
+    packages: Vec<String>,
+
+    /// Cross-compile for the given Rust target triple (e.g. `aarch64-unknown-linux-gnu`).
+    #[arg(long, value_name = "TRIPLE")]
+    target: Option<String>,
+
+    /// Produce byte-for-byte identical archives and RPMs from the same inputs. Timestamps are
+    /// taken from `SOURCE_DATE_EPOCH`, or from the last git commit when it is unset.
+    #[arg(long)]
+    reproducible: bool,
+
+    /// `json` prints a build report per package to stdout, one JSON object per line, and sends
+    /// all other output to stderr.
+    #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+    message_format: MessageFormat,
+
+    /// Write the build reports of all packages to this file as JSON.
+    #[arg(long, value_name = "PATH")]
+    report: Option<PathBuf>,
+  },
+  /// Scaffold a .spec template and a `[package.metadata.revolve]` table for the current package.
+  Init {
+    /// Write a template for `build --no-archive`, which installs the files straight from the
+    /// project directory instead of unpacking a source archive.
+    #[arg(long)]
+    no_archive: bool,
+  },
+  /// Check the configuration and the rendered .spec file for mistakes without building.
+  Lint {
+    /// Check the spec for a `build --no-archive` workflow, which must not use `%setup`.
+    #[arg(long)]
+    no_archive: bool,
+
+    /// How the findings are printed.
+    #[arg(long, value_enum, default_value_t = LintFormat::Text)]
+    format: LintFormat,
   },
   /// Display detailed information about an RPM file.
   Info {
     /// The path to the .rpm file to inspect.
     #[arg(required = true)]
     rpm_file: PathBuf,
+
+    /// How the package is printed.
+    #[arg(long, value_enum, default_value_t = InfoFormat::Table)]
+    format: InfoFormat,
+
+    #[command(flatten)]
+    query: InfoQuery,
   },
-}
+  /// Compare two RPM files, e.g. a new build against the previous release.
+  Diff {
+    /// The RPM to compare against, usually the previous release.
+    #[arg(required = true)]
+    old_rpm: PathBuf,
 
-// =================================================================================================
-// Configuration Loading Helpers
-// =================================================================================================
+    /// The RPM to check.
+    #[arg(required = true)]
+    new_rpm: PathBuf,
 
-// This struct represents the `[package.metadata]` table
-#[derive(serde::Deserialize, Debug)]
-struct MetadataToml {
-  #[serde(rename = "revolve")]
-  revolve_config: Option<RevolveConfig>,
-}
+    /// How the differences are printed.
+    #[arg(long, value_enum, default_value_t = DiffFormat::Text)]
+    format: DiffFormat,
 
-// This struct represents the `[package]` table
-#[derive(serde::Deserialize, Debug)]
-struct PackageToml {
-  metadata: Option<MetadataToml>,
-}
+    /// Exit with an error if the packages differ in this way. Can be given multiple times.
+    #[arg(long, value_enum, value_name = "CHECK")]
+    fail_on: Vec<DiffCheck>,
+  },
+  /// Unpack the files of an RPM, without `rpm2cpio` or `cpio`.
+  Extract {
+    /// The path to the .rpm file to unpack.
+    #[arg(required = true)]
+    rpm_file: PathBuf,
+
+    /// The directory to unpack into. Defaults to a directory named after the RPM file.
+    #[arg(long, value_name = "DIR")]
+    to: Option<PathBuf>,
 
-// THIS IS THE NEW TOP-LEVEL STRUCT.
-// It represents the entire Cargo.toml file, which has a `[package]` table.
-#[derive(serde::Deserialize, Debug)]
-struct Manifest {
-    package: PackageToml,
+    /// Only extract this path, or everything below it for a directory. Can be given multiple
+    /// times.
+    #[arg(long = "file", value_name = "PATH")]
+    files: Vec<PathBuf>,
+  },
 }
 
 // =================================================================================================
 // Main Application Logic
 // =================================================================================================
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
   // 1. Parse Command-Line Arguments
   let CargoCli { command: CargoCommands::Revolve(cli) } = CargoCli::parse();
 
@@ -112,76 +174,113 @@ fn main() -> Result<()> {
   env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level)).init();
   log::debug!("CLI arguments parsed: {:?}", cli);
 
-  // 3. Load Project Configuration
-  log::info!("Loading project configuration...");
-  let metadata = cargo_metadata::MetadataCommand::new()
-    .exec()
-    .context("Failed to execute `cargo metadata`")?;
-
-  let root_package = metadata
-    .root_package()
-    .ok_or_else(|| anyhow!("Could not find root package in workspace"))?;
-
-  let manifest_path = &root_package.manifest_path;
-  log::debug!("Found manifest path: {}", manifest_path);
+  // 3. Run the command; each kind of failure has its own exit code.
+  match run(cli.command) {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(e) => {
+      let code = e.exit_code();
+      eprintln!("Error: {:?}", anyhow::Error::from(e));
+      ExitCode::from(code)
+    }
+  }
+}
 
-  // 4. Dispatch to the appropriate command
-  match cli.command {
+/// Dispatches to the appropriate command.
+fn run(command: Commands) -> Result<()> {
+  match command {
     Commands::Build {
       dry_run,
       no_archive,
       verify,
+      backend,
+      workspace,
+      packages,
+      target,
+      reproducible,
+      message_format,
+      report,
     } => {
       log::debug!(
-        "Dispatching to 'build' command with dry_run={}, no_archive={}",
+        "Dispatching to 'build' command with dry_run={}, no_archive={}, backend={:?}",
         dry_run,
-        no_archive
+        no_archive,
+        backend
       );
-      
-      let current_dir = env::current_dir()?;
-      let package = metadata
-        .packages
-        .iter()
-        .find(|p| p.manifest_path.as_std_path().parent().unwrap() == current_dir)
-        .ok_or_else(|| anyhow!("Could not find a Cargo.toml in the current directory"))?;
-
-      // Get the correct target directory from the metadata.
-      let target_dir = metadata.target_directory.as_std_path();
 
-      let revolve_config = load_revolve_config(package.manifest_path.as_std_path())?;
-      // Pass the correct target_dir down to the build command.
-      commands::build::run(&revolve_config, &package.clone(), target_dir, dry_run, no_archive, verify)?;
+      // Load Project Configuration
+      log::info!("Loading project configuration...");
+      let metadata = cargo_metadata::MetadataCommand::new()
+        .exec()
+        .context("Failed to execute `cargo metadata`")?;
+
+      let options = BuildOptions::new()
+        .dry_run(dry_run)
+        .no_archive(no_archive)
+        .verify(verify)
+        .backend(backend)
+        .target(target)
+        .reproducible(reproducible)
+        .message_format(message_format);
+      let selection = if !packages.is_empty() {
+        PackageSelection::Packages(packages)
+      } else if workspace {
+        PackageSelection::Workspace
+      } else {
+        PackageSelection::Current
+      };
+
+      // Reports are kept even when the build or verification fails, so the file shows what
+      // went wrong.
+      let build = cargo_revolve::build_workspace(&metadata, &selection, &options)?;
+      if let Some(report_path) = report {
+        write_reports(&report_path, &build.reports)?;
+      }
+      build.result?;
+    }
+    Commands::Init { no_archive } => {
+      log::debug!("Dispatching to 'init' command with no_archive={}", no_archive);
+      let metadata = cargo_metadata::MetadataCommand::new()
+        .no_deps()
+        .exec()
+        .context("Failed to execute `cargo metadata`")?;
+      cli::init::run(current_package(&metadata)?, no_archive)?;
+    }
+    Commands::Lint { no_archive, format } => {
+      log::debug!("Dispatching to 'lint' command with no_archive={}", no_archive);
+      let metadata = cargo_metadata::MetadataCommand::new()
+        .exec()
+        .context("Failed to execute `cargo metadata`")?;
+      let package = current_package(&metadata)?;
+      let revolve_config = RevolveConfig::from_manifest(package.manifest_path.as_std_path())?;
+      let target_dir = metadata.target_directory.as_std_path();
+      cli::lint::run(&revolve_config, package, target_dir, no_archive, format)?;
     }
-    Commands::Info { rpm_file } => {
+    Commands::Info { rpm_file, format, query } => {
       log::debug!(
         "Dispatching to 'info' command for file: {}",
         rpm_file.display()
       );
       // The info command doesn't need project config, so we create a new module for it.
-      commands::info::run(&rpm_file)?;
+      cli::info::run(&rpm_file, format, &query)?;
+    }
+    Commands::Diff { old_rpm, new_rpm, format, fail_on } => {
+      log::debug!(
+        "Dispatching to 'diff' command for {} and {}",
+        old_rpm.display(),
+        new_rpm.display()
+      );
+      cli::diff::run(&old_rpm, &new_rpm, format, &fail_on)?;
+    }
+    Commands::Extract { rpm_file, to, files } => {
+      log::debug!("Dispatching to 'extract' command for file: {}", rpm_file.display());
+      cli::extract::run(&rpm_file, to.as_deref(), &files)?;
     }
   }
 
   Ok(())
 }
 
-fn load_revolve_config(manifest_path: &std::path::Path) -> Result<RevolveConfig> {
-  let manifest_content = fs::read_to_string(manifest_path)
-    .with_context(|| format!("Failed to read manifest file at {}", manifest_path.display()))?;
-
-  // Parse into the new, correct top-level struct
-  let manifest: Manifest =
-    toml::from_str(&manifest_content).context("Failed to parse Cargo.toml")?;
-    
-  // Now, drill down through the correct structure
-  manifest
-    .package
-    .metadata
-    .and_then(|m| m.revolve_config)
-    .ok_or_else(|| {
-      anyhow!(
-        "Missing `[package.metadata.revolve]` table in {}",
-        manifest_path.display()
-      )
-    })
-}
\ No newline at end of file
+/// Finds the package whose manifest is in the current directory.
+fn current_package(metadata: &Metadata) -> Result<&CargoPackage> {
+  Ok(select_packages(metadata, &PackageSelection::Current)?[0])
+}
@@ -0,0 +1,385 @@
+use crate::commands::build::{PackagePlan, resolve_asset_source};
+use crate::compression;
+use crate::config::{Asset, AssetKind, Dependency, DependencyOp};
+use crate::error::{Result, RevolveError};
+use crate::systemd;
+use crate::sysusers;
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, anyhow};
+use rpm::{
+  BuildConfig, Dependency as RpmDependency, DependencyFlags, FileFlags, FileMode, FileOptions,
+  PackageBuilder, Scriptlet,
+};
+
+/// The release used when the configuration does not specify one.
+const DEFAULT_RELEASE: &str = "1";
+
+/// The release of the RPMs written for `plan`.
+pub fn release<'p>(plan: &'p PackagePlan) -> &'p str {
+  plan.config.release.as_deref().unwrap_or(DEFAULT_RELEASE)
+}
+
+/// Assembles the binary RPMs (the main package plus one per subpackage) with the `rpm` crate and
+/// writes them below `rpmbuild_dir/RPMS/<arch>/`, mirroring the layout `rpmbuild` produces so
+/// artifact collection works unchanged.
+pub fn build_packages(
+  plan: &PackagePlan,
+  changelog: Option<&str>,
+  rpmbuild_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+  let package = plan.package;
+  let summary = package.description.as_deref().unwrap_or(&package.name);
+
+  let mut builder = new_builder(plan, &package.name, summary, package.description.as_deref());
+  builder = add_files(
+    builder,
+    plan,
+    plan.created_dirs.as_deref().unwrap_or_default(),
+    plan.config.assets.as_deref().unwrap_or_default(),
+  )?;
+
+  let config = &plan.config;
+  for dependency in config.requires.iter().flatten().chain(&plan.auto_requires) {
+    builder = builder.requires(to_rpm_dependency(dependency));
+  }
+  for dependency in config.provides.iter().flatten() {
+    builder = builder.provides(to_rpm_dependency(dependency));
+  }
+  for dependency in config.conflicts.iter().flatten() {
+    builder = builder.conflicts(to_rpm_dependency(dependency));
+  }
+  for dependency in config.obsoletes.iter().flatten() {
+    builder = builder.obsoletes(to_rpm_dependency(dependency));
+  }
+  if config.build_requires.is_some() {
+    log::debug!("Ignoring `build_requires`, binary RPMs built natively do not record them");
+  }
+
+  let mut scripts = plan.scripts.clone();
+  systemd::prepend_shell_scriptlets(&plan.systemd, &mut scripts);
+  sysusers::prepend_shell_scriptlet(&plan.sysusers, &mut scripts);
+  for (name, content) in scripts.iter() {
+    let scriptlet = Scriptlet::new(content).prog(vec!["/bin/sh"]);
+    builder = match name {
+      "pre" => builder.pre_install_script(scriptlet),
+      "post" => builder.post_install_script(scriptlet),
+      "preun" => builder.pre_uninstall_script(scriptlet),
+      "postun" => builder.post_uninstall_script(scriptlet),
+      "pretrans" => builder.pre_trans_script(scriptlet),
+      _ => builder.post_trans_script(scriptlet),
+    };
+  }
+
+  if let Some(content) = changelog {
+    match parse_changelog(content) {
+      Ok(entries) => {
+        for entry in entries {
+          builder = builder.add_changelog_entry(entry.name, entry.text, entry.timestamp);
+        }
+      }
+      Err(e) => {
+        // Mirror `render_spec`: a changelog problem is not worth failing the build over.
+        log::warn!("Skipping changelog, it is not in RPM changelog format: {}", e);
+      }
+    }
+  }
+
+  let main_assets = plan.config.assets.as_deref().unwrap_or_default();
+  warn_unordered_owner_dependencies(plan, &package.name, main_assets);
+  let mut written = vec![write_package(builder, plan, &package.name, rpmbuild_dir)?];
+
+  for sub in &plan.subpackages {
+    let summary = sub.summary.as_deref().unwrap_or(summary);
+    let description = sub.description.as_deref().or(sub.summary.as_deref());
+    let mut builder = new_builder(plan, &sub.full_name, summary, description);
+    builder = add_files(builder, plan, &sub.created_dirs, &sub.assets)?;
+    warn_unordered_owner_dependencies(plan, &sub.full_name, &sub.assets);
+    for requirement in sub.requires.iter().chain(&sub.auto_requires) {
+      builder = builder.requires(to_rpm_dependency(requirement));
+    }
+    written.push(write_package(builder, plan, &sub.full_name, rpmbuild_dir)?);
+  }
+
+  Ok(written)
+}
+
+/// The `rpm` crate adds a `Recommends: user(...)`/`group(...)` for every non-root owner in hash
+/// order, so a package with more than one of either differs between otherwise identical builds.
+fn warn_unordered_owner_dependencies(plan: &PackagePlan, name: &str, assets: &[Asset]) {
+  if plan.source_date_epoch.is_none() {
+    return;
+  }
+  let owners = |owner: fn(&Asset) -> Option<&String>| {
+    assets
+      .iter()
+      .filter_map(owner)
+      .filter(|owner| *owner != "root")
+      .collect::<BTreeSet<_>>()
+      .len()
+  };
+  if owners(|asset| asset.user.as_ref()) > 1 || owners(|asset| asset.group.as_ref()) > 1 {
+    log::warn!(
+      "{} has files owned by several users or groups; the native backend records their \
+       user()/group() dependencies in a random order, so the RPM is not fully reproducible.",
+      name
+    );
+  }
+}
+
+/// Starts a package builder carrying the metadata shared by the main package and subpackages.
+fn new_builder(
+  plan: &PackagePlan,
+  name: &str,
+  summary: &str,
+  description: Option<&str>,
+) -> PackageBuilder {
+  let package = plan.package;
+  let version = package.version.to_string();
+  let release = release(plan);
+  let license = package.license.as_deref().unwrap_or("N/A");
+
+  log::info!("Assembling RPM for {}-{}-{} with the native backend...", name, version, release);
+
+  let mut builder = PackageBuilder::new(name, &version, license, plan.rpm_arch, summary)
+    .release(release);
+  if let Some(description) = description {
+    builder = builder.description(description);
+  }
+  if let Some(homepage) = &package.homepage {
+    builder = builder.url(homepage);
+  }
+  if let Some(format) = plan.config.payload_compression {
+    let payload = compression::rpm_payload(format, plan.config.payload_compression_level);
+    builder = builder.using_config(BuildConfig::default().compression(payload));
+  }
+  if let Some(epoch) = plan.source_date_epoch {
+    // Clamps file mtimes and the build time, which would otherwise change on every build.
+    builder = builder.source_date(epoch).build_host("reproducible");
+  }
+  builder
+}
+
+/// Adds the owned directories and the (already expanded) file assets to a package.
+fn add_files(
+  mut builder: PackageBuilder,
+  plan: &PackagePlan,
+  created_dirs: &[String],
+  assets: &[Asset],
+) -> Result<PackageBuilder> {
+  let project_dir = plan.manifest_dir();
+
+  for dir in created_dirs {
+    log::debug!("Adding directory entry: {}", dir);
+    builder = builder.with_file_contents(
+      Vec::new(),
+      FileOptions::new(dir.trim_end_matches('/')).mode(FileMode::dir(0o755)),
+    )?;
+  }
+
+  for asset in assets {
+    builder = add_asset(builder, asset, project_dir, plan.target_dir)?;
+  }
+  Ok(builder)
+}
+
+/// Builds the package and writes it as `RPMS/<arch>/<name>-<version>-<release>.<arch>.rpm`.
+fn write_package(
+  builder: PackageBuilder,
+  plan: &PackagePlan,
+  name: &str,
+  rpmbuild_dir: &Path,
+) -> Result<PathBuf> {
+  let rpm_package = builder
+    .build()
+    .with_context(|| format!("Failed to assemble the {} RPM with the native backend", name))?;
+
+  let release = release(plan);
+  let rpm_filename = format!("{}-{}-{}.{}.rpm", name, plan.package.version, release, plan.rpm_arch);
+  let arch_dir = rpmbuild_dir.join("RPMS").join(plan.rpm_arch);
+  fs::create_dir_all(&arch_dir)
+    .with_context(|| format!("Failed to create RPM output directory at {}", arch_dir.display()))?;
+  let rpm_path = arch_dir.join(rpm_filename);
+
+  rpm_package
+    .write_file(&rpm_path)
+    .with_context(|| format!("Failed to write RPM to {}", rpm_path.display()))?;
+
+  log::info!("Native backend wrote {}", rpm_path.display());
+  Ok(rpm_path)
+}
+
+/// Converts a declared dependency into its RPM header form.
+fn to_rpm_dependency(dependency: &Dependency) -> RpmDependency {
+  let name = dependency.name.as_str();
+  let version = dependency.version.clone().unwrap_or_default();
+  match dependency.op {
+    None => RpmDependency::any(name),
+    Some(DependencyOp::Eq) => RpmDependency::eq(name, version),
+    Some(DependencyOp::Lt) => RpmDependency::less(name, version),
+    Some(DependencyOp::Le) => RpmDependency::less_eq(name, version),
+    Some(DependencyOp::Gt) => RpmDependency::greater(name, version),
+    Some(DependencyOp::Ge) => RpmDependency::greater_eq(name, version),
+  }
+}
+
+/// Whether a dependency read from an RPM header is the declared one. Only the comparison bits of
+/// the flags are compared, so markers rpmbuild adds (e.g. for scriptlet requirements) are ignored.
+pub fn dependency_matches(declared: &Dependency, found: &RpmDependency) -> bool {
+  let sense = DependencyFlags::LESS | DependencyFlags::GREATER | DependencyFlags::EQUAL;
+  let expected = to_rpm_dependency(declared);
+  found.name == expected.name
+    && (found.flags & sense) == (expected.flags & sense)
+    && found.version == expected.version
+}
+
+/// The `%files` flags an asset kind sets on its file entry.
+pub fn file_flags(kind: AssetKind) -> FileFlags {
+  match kind {
+    AssetKind::Config => FileFlags::CONFIG,
+    AssetKind::ConfigNoreplace => FileFlags::CONFIG | FileFlags::NOREPLACE,
+    AssetKind::Doc => FileFlags::DOC,
+    AssetKind::License => FileFlags::LICENSE,
+    AssetKind::Ghost => FileFlags::GHOST,
+    AssetKind::Dir => FileFlags::empty(),
+  }
+}
+
+/// All flags that `file_flags` can produce, for comparing them with a file entry.
+pub fn file_flags_mask() -> FileFlags {
+  FileFlags::CONFIG | FileFlags::NOREPLACE | FileFlags::DOC | FileFlags::LICENSE | FileFlags::GHOST
+}
+
+fn add_asset(
+  builder: PackageBuilder,
+  asset: &Asset,
+  project_dir: &Path,
+  target_dir: &Path,
+) -> Result<PackageBuilder> {
+  let mode = match &asset.mode {
+    Some(mode_str) => Some(u16::from_str_radix(mode_str, 8).map_err(|_| {
+      RevolveError::Asset(format!("Invalid octal mode '{}' for asset {}", mode_str, asset.dest))
+    })?),
+    None => None,
+  };
+
+  let mut options = FileOptions::new(asset.dest.trim_end_matches('/'));
+  if let Some(user) = &asset.user {
+    options = options.user(user);
+  }
+  if let Some(group) = &asset.group {
+    options = options.group(group);
+  }
+  options = match asset.kind {
+    None | Some(AssetKind::Dir) => options,
+    Some(AssetKind::Config) => options.is_config(),
+    Some(AssetKind::ConfigNoreplace) => options.is_config_noreplace(),
+    Some(AssetKind::Doc) => options.is_doc(),
+    Some(AssetKind::License) => options.is_license(),
+    Some(AssetKind::Ghost) => options.is_ghost(),
+  };
+
+  if asset.kind == Some(AssetKind::Dir) {
+    log::debug!("Adding directory asset: {}", asset.dest);
+    return Ok(builder
+      .with_file_contents(Vec::new(), options.mode(FileMode::dir(mode.unwrap_or(0o755))))
+      .with_context(|| format!("Failed to add directory {} to the RPM", asset.dest))?);
+  }
+  if let Some(target) = &asset.symlink_target {
+    log::debug!("Adding symlink {} -> {}", asset.dest, target);
+    // The cpio payload stores a symlink's target as its contents.
+    return Ok(builder
+      .with_file_contents(
+        target.as_bytes().to_vec(),
+        options.symlink(target).mode(FileMode::symbolic_link(0o777)),
+      )
+      .with_context(|| format!("Failed to add symlink {} to the RPM", asset.dest))?);
+  }
+  if let Some(mode) = mode {
+    options = options.mode(FileMode::regular(mode));
+  }
+  if !asset.has_source() {
+    // A ghost without a source: only its attributes are recorded.
+    log::debug!("Adding ghost file: {}", asset.dest);
+    return Ok(builder
+      .with_file_contents(Vec::new(), options)
+      .with_context(|| format!("Failed to add ghost file {} to the RPM", asset.dest))?);
+  }
+
+  let source_path = resolve_asset_source(&asset.source, project_dir, target_dir);
+  if !source_path.exists() {
+    return Err(RevolveError::MissingAssetSource(source_path));
+  }
+
+  log::debug!("Adding file {} -> {}", source_path.display(), asset.dest);
+  Ok(builder
+    .with_file(&source_path, options)
+    .with_context(|| format!("Failed to add {} to the RPM", source_path.display()))?)
+}
+
+/// A single parsed `%changelog` entry.
+struct ChangelogEntry {
+  name: String,
+  text: String,
+  timestamp: u32,
+}
+
+/// Parses changelog text in `.spec` format:
+///
+/// ```text
+/// * Mon Jan 01 2024 Jane Doe <jane@example.com> - 1.0.0-1
+/// - Did a thing.
+/// ```
+fn parse_changelog(content: &str) -> anyhow::Result<Vec<ChangelogEntry>> {
+  let mut entries: Vec<ChangelogEntry> = Vec::new();
+
+  for line in content.lines() {
+    if let Some(header) = line.strip_prefix("* ") {
+      let mut parts = header.split_whitespace();
+      let (_weekday, month, day, year) = (parts.next(), parts.next(), parts.next(), parts.next());
+      let timestamp = match (month, day, year) {
+        (Some(m), Some(d), Some(y)) => parse_changelog_date(m, d, y)
+          .ok_or_else(|| anyhow!("invalid date in changelog header '{}'", line))?,
+        _ => return Err(anyhow!("incomplete changelog header '{}'", line)),
+      };
+      let name = parts.collect::<Vec<_>>().join(" ");
+      entries.push(ChangelogEntry { name, text: String::new(), timestamp });
+    } else if let Some(entry) = entries.last_mut() {
+      if !entry.text.is_empty() {
+        entry.text.push('\n');
+      }
+      entry.text.push_str(line);
+    } else if !line.trim().is_empty() {
+      return Err(anyhow!("expected a '* <date> <author>' header, found '{}'", line));
+    }
+  }
+
+  for entry in &mut entries {
+    entry.text = entry.text.trim_end().to_string();
+  }
+  Ok(entries)
+}
+
+/// Converts a changelog date (`Jan 01 2024`) into a UNIX timestamp at noon UTC, as rpmbuild does.
+fn parse_changelog_date(month: &str, day: &str, year: &str) -> Option<u32> {
+  const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+  ];
+  let month = MONTHS.iter().position(|m| *m == month)? as i64 + 1;
+  let day: i64 = day.parse().ok().filter(|d| (1..=31).contains(d))?;
+  let year: i64 = year.parse().ok()?;
+
+  // Days since the epoch for a proleptic Gregorian date (Howard Hinnant's algorithm).
+  let y = if month <= 2 { year - 1 } else { year };
+  let era = y.div_euclid(400);
+  let yoe = y - era * 400;
+  let mp = (month + 9) % 12;
+  let doy = (153 * mp + 2) / 5 + day - 1;
+  let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+  let days = era * 146_097 + doe - 719_468;
+
+  u32::try_from(days * 86_400 + 12 * 3_600).ok()
+}
@@ -0,0 +1,144 @@
+use crate::commands::build::Backend;
+use crate::error::{Result, RevolveError};
+
+use anyhow::Context;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// What a `build` of one package did and produced, for release pipelines that upload or sign
+/// the RPMs. Printed by `--message-format json` and written by `--report`.
+#[derive(Serialize, Debug, Clone)]
+pub struct BuildReport {
+  pub name: String,
+  pub version: String,
+  /// The release of the main binary RPM. Unknown on a dry run with the `rpmbuild` backend,
+  /// where it is only decided by the .spec file.
+  pub release: Option<String>,
+  /// The architecture of the main binary RPM, e.g. `x86_64` or `noarch`.
+  pub arch: String,
+  pub backend: Backend,
+  pub dry_run: bool,
+  /// The rendered .spec file; the native backend does not use one.
+  pub spec: Option<PathBuf>,
+  /// The source archive handed to `rpmbuild`, if one was written.
+  pub archive: Option<PathBuf>,
+  /// The external commands that were run, in order.
+  pub commands: Vec<CommandReport>,
+  pub artifacts: Vec<ArtifactReport>,
+  /// One entry per checked package with `--verify`, `None` without it.
+  pub verification: Option<Vec<VerificationReport>>,
+  /// Why the build failed, `None` if it did not. A failed report only holds what was known
+  /// before the build started.
+  pub error: Option<ErrorReport>,
+}
+
+/// The error that stopped the build of a package.
+#[derive(Serialize, Debug, Clone)]
+pub struct ErrorReport {
+  /// The exit code of the command-line tool for this error.
+  pub exit_code: u8,
+  /// The error and its causes on one line.
+  pub message: String,
+}
+
+/// An external command run during a build, such as `cargo build` or `rpmbuild`.
+#[derive(Serialize, Debug, Clone)]
+pub struct CommandReport {
+  pub command: String,
+  pub duration_secs: f64,
+}
+
+impl CommandReport {
+  pub(crate) fn new(command: String, duration: Duration) -> Self {
+    CommandReport {
+      command,
+      duration_secs: duration.as_secs_f64(),
+    }
+  }
+}
+
+/// A collected RPM file.
+#[derive(Serialize, Debug, Clone)]
+pub struct ArtifactReport {
+  pub path: PathBuf,
+  /// The package name from the RPM header.
+  pub name: String,
+  pub release: String,
+  pub arch: String,
+  pub size: u64,
+  /// The hex-encoded SHA-256 digest of the file.
+  pub sha256: String,
+}
+
+impl ArtifactReport {
+  /// Reads the header of the RPM at `path` and hashes the file.
+  pub fn read(path: &Path) -> Result<Self> {
+    let info = crate::commands::info::RpmInfo::read(path)?;
+    let mut file = fs::File::open(path)
+      .with_context(|| format!("Failed to open {} for hashing", path.display()))?;
+    let mut hasher = Sha256::new();
+    let size = io::copy(&mut file, &mut hasher)
+      .with_context(|| format!("Failed to hash {}", path.display()))?;
+    Ok(ArtifactReport {
+      path: path.to_path_buf(),
+      name: info.name,
+      release: info.release,
+      arch: info.arch,
+      size,
+      sha256: format!("{:x}", hasher.finalize()),
+    })
+  }
+}
+
+/// The `--verify` result of one package.
+#[derive(Serialize, Debug, Clone)]
+pub struct VerificationReport {
+  pub package: String,
+  /// The RPM that was checked, if one was found for the package.
+  pub rpm: Option<PathBuf>,
+  pub passed: bool,
+  pub error: Option<String>,
+}
+
+impl BuildReport {
+  /// Fails if `--verify` found a problem with any of the packages.
+  pub fn check_verification(&self) -> Result<()> {
+    let failures: Vec<&VerificationReport> =
+      self.verification.iter().flatten().filter(|v| !v.passed).collect();
+    match failures.as_slice() {
+      [] => Ok(()),
+      [failure] => Err(RevolveError::Verification(failure.error.clone().unwrap_or_default())),
+      _ => {
+        for failure in &failures {
+          log::error!("{}: {}", failure.package, failure.error.as_deref().unwrap_or_default());
+        }
+        let names: Vec<&str> = failures.iter().map(|f| f.package.as_str()).collect();
+        Err(RevolveError::Verification(format!(
+          "Verification failed for {} packages: {}",
+          failures.len(),
+          names.join(", ")
+        )))
+      }
+    }
+  }
+}
+
+/// The file written by `build --report`: the reports of every package that was built, including
+/// those whose verification failed.
+#[derive(Serialize)]
+struct ReportFile<'a> {
+  packages: &'a [BuildReport],
+}
+
+/// Writes `reports` to `path` as a JSON object with a `packages` array.
+pub fn write_reports(path: &Path, reports: &[BuildReport]) -> Result<()> {
+  let json = serde_json::to_string_pretty(&ReportFile { packages: reports })
+    .context("Failed to serialize the build report")?;
+  fs::write(path, json)
+    .with_context(|| format!("Failed to write the build report to {}", path.display()))?;
+  Ok(())
+}
@@ -0,0 +1,167 @@
+use crate::config::{Asset, Dependency};
+use crate::definitions::{ScriptsContext, SystemdContext};
+use crate::error::{Result, RevolveError};
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail};
+
+/// Where units are installed; the value of `%{_unitdir}` on RPM-based distributions.
+const UNIT_DIR: &str = "/usr/lib/systemd/system";
+
+/// The package providing the `%systemd_*` macros used by the generated scriptlets.
+const MACROS_PACKAGE: &str = "systemd-rpm-macros";
+
+/// Validates the configured unit files and prepares the scriptlet snippets for them.
+pub fn plan_units(unit_sources: &[String], project_dir: &Path) -> Result<SystemdContext> {
+  let mut units = Vec::with_capacity(unit_sources.len());
+  for source in unit_sources {
+    let unit_path = project_dir.join(source);
+    let name = unit_path
+      .file_name()
+      .and_then(|name| name.to_str())
+      .ok_or_else(|| RevolveError::Config(format!("Invalid systemd unit path '{}'", source)))?
+      .to_string();
+    let content = fs::read_to_string(&unit_path).map_err(|e| {
+      RevolveError::Config(format!("Failed to read systemd unit at {}: {}", unit_path.display(), e))
+    })?;
+    validate_unit(&name, &content).map_err(|e| {
+      RevolveError::Config(format!("Invalid systemd unit {}: {}", unit_path.display(), e))
+    })?;
+    units.push(name);
+  }
+
+  let macro_line = |name: &str| {
+    if units.is_empty() {
+      String::new()
+    } else {
+      format!("%{} {}", name, units.join(" "))
+    }
+  };
+  Ok(SystemdContext {
+    post: macro_line("systemd_post"),
+    preun: macro_line("systemd_preun"),
+    postun: macro_line("systemd_postun_with_restart"),
+    units,
+  })
+}
+
+/// The assets that install each unit into the unit directory. The directory itself belongs to
+/// systemd, so it is never owned by the package.
+pub fn unit_assets(unit_sources: &[String], systemd: &SystemdContext) -> Vec<Asset> {
+  unit_sources
+    .iter()
+    .zip(&systemd.units)
+    .map(|(source, name)| Asset {
+      source: source.clone(),
+      dest: format!("{}/{}", UNIT_DIR, name),
+      mode: Some("0644".to_string()),
+      user: None,
+      group: None,
+      mkdir: false,
+      kind: None,
+      symlink_target: None,
+      preserve_symlinks: false,
+      exclude: None,
+      gitignore: false,
+      archive_path: None,
+      source_path: None,
+    })
+    .collect()
+}
+
+/// Adds `BuildRequires: systemd-rpm-macros` unless it is already declared.
+pub fn add_macros_build_requirement(build_requires: &mut Vec<Dependency>) {
+  if !build_requires.iter().any(|dep| dep.name == MACROS_PACKAGE) {
+    build_requires.push(Dependency {
+      name: MACROS_PACKAGE.to_string(),
+      op: None,
+      version: None,
+    });
+  }
+}
+
+/// Prepends the shell equivalent of the `%systemd_*` macros to the scriptlets, for the native
+/// backend which has no macro expansion.
+pub fn prepend_shell_scriptlets(systemd: &SystemdContext, scripts: &mut ScriptsContext) {
+  if systemd.units.is_empty() {
+    return;
+  }
+  let units = systemd.units.join(" ");
+  let prepend = |script: &mut Option<String>, snippet: String| {
+    *script = Some(match script.take() {
+      Some(existing) => format!("{}\n{}", snippet, existing),
+      None => snippet,
+    });
+  };
+
+  prepend(
+    &mut scripts.post,
+    format!(
+      "if [ $1 -eq 1 ]; then\n  systemctl --no-reload preset {} >/dev/null 2>&1 || :\nfi\n",
+      units
+    ),
+  );
+  prepend(
+    &mut scripts.preun,
+    format!(
+      "if [ $1 -eq 0 ]; then\n  systemctl --no-reload disable --now {} >/dev/null 2>&1 || :\nfi\n",
+      units
+    ),
+  );
+  prepend(
+    &mut scripts.postun,
+    format!(
+      "systemctl daemon-reload >/dev/null 2>&1 || :\nif [ $1 -ge 1 ]; then\n  systemctl try-restart {} >/dev/null 2>&1 || :\nfi\n",
+      units
+    ),
+  );
+}
+
+/// Checks a unit file closely enough to catch mistakes that would otherwise only show up on
+/// the target machine: unknown unit types, a missing type section (e.g. `[Service]`), and lines
+/// that are neither a section header nor a `Key=Value` assignment.
+fn validate_unit(name: &str, content: &str) -> anyhow::Result<()> {
+  let unit_type = name.rsplit_once('.').map(|(_, ext)| ext).unwrap_or_default();
+  let required_section = match unit_type {
+    "service" => Some("Service"),
+    "socket" => Some("Socket"),
+    "timer" => Some("Timer"),
+    "path" => Some("Path"),
+    "mount" => Some("Mount"),
+    "automount" => Some("Automount"),
+    "swap" => Some("Swap"),
+    "target" | "slice" => None,
+    _ => bail!("'{}' is not a systemd unit file name (e.g. `my-app.service`)", name),
+  };
+
+  let mut sections = Vec::new();
+  let mut continued = false;
+  for (index, raw_line) in content.lines().enumerate() {
+    let line = raw_line.trim();
+    let is_continuation = continued;
+    continued = line.ends_with('\\');
+    if is_continuation || line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+      continue;
+    }
+
+    if let Some(section) = line.strip_prefix('[') {
+      let section = section
+        .strip_suffix(']')
+        .ok_or_else(|| anyhow!("line {}: unterminated section header '{}'", index + 1, line))?;
+      sections.push(section.to_string());
+    } else if !line.contains('=') {
+      bail!("line {}: expected 'Key=Value', found '{}'", index + 1, line);
+    } else if sections.is_empty() {
+      bail!("line {}: '{}' appears before any section header", index + 1, line);
+    }
+  }
+
+  if let Some(section) = required_section
+    && !sections.iter().any(|s| s == section)
+  {
+    bail!("a .{} unit must have a [{}] section", unit_type, section);
+  }
+  Ok(())
+}
@@ -0,0 +1,162 @@
+use crate::config::{Asset, User};
+use crate::definitions::{ScriptsContext, SysusersContext};
+use crate::error::{Result, RevolveError};
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Where sysusers.d files are installed; the value of `%{_sysusersdir}`.
+const SYSUSERS_DIR: &str = "/usr/lib/sysusers.d";
+
+/// The shell given to accounts that do not set one.
+const DEFAULT_SHELL: &str = "/sbin/nologin";
+
+/// Validates the `users` entries and renders the sysusers.d file for them.
+///
+/// The file is written by [`write_file`] below the Cargo target directory, where asset sources
+/// starting with `target/` are resolved, so it can be packaged like any other build artifact.
+pub fn plan_users(package_name: &str, users: &[User], target_dir: &Path) -> Result<SysusersContext> {
+  if users.is_empty() {
+    return Ok(SysusersContext::default());
+  }
+
+  for user in users {
+    if !user.system {
+      return Err(RevolveError::Config(format!(
+        "User '{}' must be a system user; sysusers.d cannot create regular accounts.",
+        user.name
+      )));
+    }
+    for (field, value) in [("name", Some(&user.name)), ("group", user.group.as_ref())] {
+      if let Some(value) = value
+        && (value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == ':'))
+      {
+        return Err(RevolveError::Config(format!("Invalid {} '{}' in `users`", field, value)));
+      }
+    }
+  }
+
+  // Users share groups, but each group is only declared once. A user without a `group` gets a
+  // group of its own name from its `u` line.
+  let own_groups: BTreeSet<&str> = users
+    .iter()
+    .filter(|user| user.group.as_deref().is_none_or(|group| group == user.name))
+    .map(|user| user.name.as_str())
+    .collect();
+  let shared_groups: BTreeSet<&str> = users
+    .iter()
+    .filter_map(|user| user.group.as_deref())
+    .filter(|group| !own_groups.contains(group))
+    .collect();
+
+  let mut content = format!("# Generated by cargo-revolve for {}.\n", package_name);
+  for group in &shared_groups {
+    content.push_str(&format!("g {} -\n", group));
+  }
+  for user in users {
+    let group = user_group(user);
+    let id = if group == user.name {
+      "-".to_string()
+    } else {
+      format!("-:{}", group)
+    };
+    content.push_str(&format!(
+      "u {} {} \"{}\" {} {}\n",
+      user.name,
+      id,
+      user.name,
+      user.home.as_deref().unwrap_or("-"),
+      user.shell.as_deref().unwrap_or(DEFAULT_SHELL),
+    ));
+  }
+
+  let file_name = format!("{}.conf", package_name);
+  let source = format!("target/revolve-generated/{}/{}", package_name, file_name);
+  let source_path = target_dir.join(source.trim_start_matches("target/"));
+  Ok(SysusersContext {
+    users: users.to_vec(),
+    file: Some(format!("{}/{}", SYSUSERS_DIR, file_name)),
+    pre: format!("%sysusers_create_compat {}", source_path.display()),
+    source,
+    source_path,
+    content,
+  })
+}
+
+/// The asset that installs the generated file into the sysusers.d directory, which belongs to
+/// systemd and is never owned by the package.
+pub fn sysusers_asset(sysusers: &SysusersContext) -> Option<Asset> {
+  Some(Asset {
+    source: sysusers.source.clone(),
+    dest: sysusers.file.clone()?,
+    mode: Some("0644".to_string()),
+    user: None,
+    group: None,
+    mkdir: false,
+    kind: None,
+    symlink_target: None,
+    preserve_symlinks: false,
+    exclude: None,
+    gitignore: false,
+    archive_path: None,
+    source_path: None,
+  })
+}
+
+/// The primary group of `user`, which defaults to a group named after it.
+fn user_group(user: &User) -> &str {
+  user.group.as_deref().unwrap_or(&user.name)
+}
+
+/// Writes the generated sysusers.d file, if any users are configured.
+pub fn write_file(sysusers: &SysusersContext) -> Result<()> {
+  if sysusers.users.is_empty() {
+    return Ok(());
+  }
+  if let Some(parent) = sysusers.source_path.parent() {
+    fs::create_dir_all(parent).map_err(|e| {
+      RevolveError::Config(format!("Failed to create directory at {}: {}", parent.display(), e))
+    })?;
+  }
+  log::info!("Writing sysusers.d file to {}", sysusers.source_path.display());
+  fs::write(&sysusers.source_path, &sysusers.content).map_err(|e| {
+    RevolveError::Config(format!(
+      "Failed to write sysusers.d file to {}: {}",
+      sysusers.source_path.display(),
+      e
+    ))
+  })?;
+  Ok(())
+}
+
+/// Prepends the shell equivalent of `%sysusers_create_compat` to the `%pre` scriptlet, for the
+/// native backend which has no macro expansion.
+pub fn prepend_shell_scriptlet(sysusers: &SysusersContext, scripts: &mut ScriptsContext) {
+  if sysusers.users.is_empty() {
+    return;
+  }
+
+  let mut snippet = String::new();
+  let groups: BTreeSet<&str> = sysusers.users.iter().map(user_group).collect();
+  for group in groups {
+    snippet.push_str(&format!(
+      "getent group {0} >/dev/null || groupadd -r {0}\n",
+      group
+    ));
+  }
+  for user in &sysusers.users {
+    snippet.push_str(&format!(
+      "getent passwd {0} >/dev/null || useradd -r -g {1} -d {2} -s {3} -c '{0}' {0}\n",
+      user.name,
+      user_group(user),
+      user.home.as_deref().unwrap_or("/"),
+      user.shell.as_deref().unwrap_or(DEFAULT_SHELL),
+    ));
+  }
+
+  scripts.pre = Some(match scripts.pre.take() {
+    Some(existing) => format!("{}\n{}", snippet, existing),
+    None => snippet,
+  });
+}
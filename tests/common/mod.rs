@@ -1,4 +1,9 @@
+// Each test binary uses only some of these helpers.
+#![allow(dead_code)]
+
 use assert_cmd::Command;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 /// Helper to create the command correctly, simulating `cargo revolve ...`.
 /// This is the key fix for the original panic.
@@ -6,4 +11,34 @@ pub fn create_revolve_command() -> Command {
   let mut cmd = Command::cargo_bin("cargo-revolve").unwrap();
   cmd.arg("revolve");
   cmd
-}
\ No newline at end of file
+}
+
+/// Returns the only `.rpm` file in `dir`, usually a fixture's `dist/` directory.
+pub fn find_rpm(dir: &Path) -> PathBuf {
+  let rpms: Vec<PathBuf> = fs::read_dir(dir)
+    .unwrap_or_else(|e| panic!("Cannot read {}: {}", dir.display(), e))
+    .map(|e| e.unwrap().path())
+    .filter(|p| p.extension().is_some_and(|ext| ext == "rpm"))
+    .collect();
+  match rpms.as_slice() {
+    [rpm] => rpm.clone(),
+    _ => panic!("Expected one RPM in {}, found {:?}", dir.display(), rpms),
+  }
+}
+
+/// Copies the fixture project `from` to `to`, without its build output.
+pub fn copy_dir(from: &Path, to: &Path) {
+  fs::create_dir_all(to).unwrap();
+  for entry in fs::read_dir(from).unwrap() {
+    let entry = entry.unwrap();
+    let name = entry.file_name();
+    if name == "target" || name == "dist" {
+      continue;
+    }
+    if entry.file_type().unwrap().is_dir() {
+      copy_dir(&entry.path(), &to.join(name));
+    } else {
+      fs::copy(entry.path(), to.join(name)).unwrap();
+    }
+  }
+}
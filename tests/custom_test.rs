@@ -52,7 +52,7 @@ fn test_custom_build_command_happy_path() {
   let rpm_files: Vec<_> = walkdir::WalkDir::new(&rpm_path)
     .into_iter()
     .filter_map(|e| e.ok())
-    .filter(|e| e.path().extension().map_or(false, |ext| ext == "rpm"))
+    .filter(|e| e.path().extension().is_some_and(|ext| ext == "rpm"))
     .collect();
   assert!(!rpm_files.is_empty(), "Expected an RPM to be built after custom command");
 }
\ No newline at end of file
@@ -0,0 +1,120 @@
+mod common;
+
+use common::create_revolve_command;
+use rpm::{Dependency, FileOptions, PackageBuilder};
+use serde_json::{Value, json};
+use std::path::PathBuf;
+
+/// Writes two releases of a small package to a directory named after the test and returns their
+/// paths.
+fn write_rpms(test: &str) -> (PathBuf, PathBuf) {
+  let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test);
+  std::fs::create_dir_all(&dir).unwrap();
+
+  let old = PackageBuilder::new("my-app", "0.1.0", "MIT", "x86_64", "My app")
+    .release("1")
+    .with_file_contents("v1", FileOptions::new("/usr/bin/my-app").mode(0o100755))
+    .unwrap()
+    .with_file_contents("a = 1", FileOptions::new("/etc/my-app.conf").is_config().mode(0o100644))
+    .unwrap()
+    .with_file_contents("docs", FileOptions::new("/usr/share/doc/my-app/README.md"))
+    .unwrap()
+    .requires(Dependency::any("ca-certificates"))
+    .post_install_script("echo installed")
+    .build()
+    .unwrap();
+  let new = PackageBuilder::new("my-app", "0.2.0", "MIT", "x86_64", "My app")
+    .release("1")
+    .with_file_contents("v2", FileOptions::new("/usr/bin/my-app").mode(0o100755))
+    .unwrap()
+    .with_file_contents("a = 1", FileOptions::new("/etc/my-app.conf").is_config().mode(0o100600))
+    .unwrap()
+    .with_file_contents("data", FileOptions::new("/usr/share/my-app/data.json"))
+    .unwrap()
+    .requires(Dependency::greater_eq("openssl-libs", "3.0"))
+    .post_install_script("echo upgraded")
+    .pre_uninstall_script("echo removing")
+    .build()
+    .unwrap();
+
+  let (old_path, new_path) = (dir.join("old.rpm"), dir.join("new.rpm"));
+  old.write_file(&old_path).unwrap();
+  new.write_file(&new_path).unwrap();
+  (old_path, new_path)
+}
+
+#[test]
+fn test_diff_reports_all_changes() {
+  let (old, new) = write_rpms("diff_reports_all_changes");
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd.arg("diff").arg(&old).arg(&new).assert().success();
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+
+  let lines: Vec<&str> = output.lines().collect();
+  assert!(lines.contains(&"  version: 0.1.0 -> 0.2.0"), "{}", output);
+  assert!(lines.contains(&"  - /usr/share/doc/my-app/README.md"), "{}", output);
+  assert!(lines.contains(&"  + /usr/share/my-app/data.json"), "{}", output);
+  assert!(lines.contains(&"  ~ /etc/my-app.conf: mode 0644 -> 0600"), "{}", output);
+  let binary = lines.iter().find(|line| line.starts_with("  ~ /usr/bin/my-app: ")).unwrap();
+  assert!(binary.contains("digest "), "{}", binary);
+  assert!(lines.contains(&"  - ca-certificates"), "{}", output);
+  assert!(lines.contains(&"  + openssl-libs >= 3.0"), "{}", output);
+  assert!(lines.contains(&"  ~ post"), "{}", output);
+  assert!(lines.contains(&"  + preun"), "{}", output);
+}
+
+#[test]
+fn test_diff_of_identical_rpms() {
+  let (old, _) = write_rpms("diff_of_identical_rpms");
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .arg("diff")
+    .arg(&old)
+    .arg(&old)
+    .args(["--fail-on", "any"])
+    .assert()
+    .success();
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.ends_with("\nNo differences.\n"), "{}", output);
+}
+
+#[test]
+fn test_diff_json() {
+  let (old, new) = write_rpms("diff_json");
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd.arg("diff").arg(&old).arg(&new).args(["--format", "json"]).assert().success();
+  let diff: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+
+  assert_eq!(diff["header"], json!([{ "field": "version", "old": "0.1.0", "new": "0.2.0" }]));
+  assert_eq!(diff["removed_files"][0]["path"], "/usr/share/doc/my-app/README.md");
+  assert_eq!(diff["added_files"][0]["path"], "/usr/share/my-app/data.json");
+  assert_eq!(diff["changed_files"].as_array().unwrap().len(), 2);
+  assert_eq!(
+    diff["requires"]["added"],
+    json!([{ "name": "openssl-libs", "op": ">=", "version": "3.0" }])
+  );
+  assert_eq!(diff["scripts"][0]["name"], "post");
+  assert_eq!(diff["scripts"][0]["new"]["body"], "echo upgraded");
+  assert!(diff["scripts"][1]["old"].is_null());
+}
+
+#[test]
+fn test_diff_fail_on() {
+  let (old, new) = write_rpms("diff_fail_on");
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .arg("diff")
+    .arg(&old)
+    .arg(&new)
+    .args(["--fail-on", "removed-files", "--fail-on", "scripts"])
+    .assert()
+    .failure()
+    .code(11);
+  let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+  assert!(stderr.contains("1 removed file(s), 2 scriptlet change(s)"), "{}", stderr);
+
+}
@@ -0,0 +1,175 @@
+mod common;
+
+use common::{copy_dir, create_revolve_command};
+use serial_test::serial;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const ERROR_FIXTURE_DIR: &str = "tests/fixtures/error-project";
+
+/// Copies the fixture to a fresh directory and appends `revolve` to its manifest as the
+/// `[package.metadata.revolve]` table.
+fn project_with(name: &str, revolve: &str) -> PathBuf {
+  let project_dir = std::env::temp_dir().join(format!("cargo-revolve-error-{}", name));
+  let _ = fs::remove_dir_all(&project_dir);
+  copy_dir(Path::new(ERROR_FIXTURE_DIR), &project_dir);
+
+  let manifest_path = project_dir.join("Cargo.toml");
+  let mut manifest = fs::read_to_string(&manifest_path).unwrap();
+  if !revolve.is_empty() {
+    manifest.push_str("\n[package.metadata.revolve]\n");
+    manifest.push_str(revolve);
+  }
+  fs::write(&manifest_path, manifest).unwrap();
+  project_dir
+}
+
+/// Runs `build` with `args` in `project_dir`, checks the exit code and returns stderr.
+fn build_fails_with(project_dir: &Path, args: &[&str], code: i32) -> String {
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(project_dir)
+    .arg("build")
+    .args(args)
+    .assert()
+    .failure()
+    .code(code);
+  let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+  let _ = fs::remove_dir_all(project_dir);
+  stderr
+}
+
+#[test]
+#[serial]
+fn test_missing_config_exits_with_config_code() {
+  let project_dir = project_with("missing-config", "");
+
+  let stderr = build_fails_with(&project_dir, &["--dry-run"], 3);
+  assert!(stderr.contains("Missing `[package.metadata.revolve]` table"));
+}
+
+#[test]
+#[serial]
+fn test_invalid_manifest_exits_with_config_code() {
+  let project_dir = project_with("invalid-manifest", "spec_template = 42\n");
+
+  let stderr = build_fails_with(&project_dir, &["--dry-run"], 3);
+  assert!(stderr.contains("Failed to load manifest file at"));
+}
+
+#[test]
+#[serial]
+fn test_template_error_exits_with_template_code() {
+  let project_dir = project_with("template", "spec_template = \"broken.spec.in\"\n");
+
+  let stderr = build_fails_with(&project_dir, &["--dry-run"], 4);
+  assert!(stderr.contains("Failed to render spec template"));
+}
+
+#[test]
+#[serial]
+fn test_duplicate_destination_exits_with_asset_code() {
+  let project_dir = project_with(
+    "duplicate",
+    r#"spec_template = "error.spec.in"
+assets = [
+  { source = "config/", dest = "/etc/error-project/" },
+  { source = "config/app.toml", dest = "/etc/error-project/app.toml" },
+]
+"#,
+  );
+
+  let stderr = build_fails_with(&project_dir, &["--dry-run"], 5);
+  assert!(stderr.contains("Duplicate asset destination found: '/etc/error-project/app.toml'"));
+  assert!(stderr.contains("- Provided by source 'config/'"));
+  assert!(stderr.contains("- Also provided by source 'config/app.toml'"));
+}
+
+#[test]
+#[serial]
+fn test_file_marked_as_directory_exits_with_asset_code() {
+  let project_dir = project_with(
+    "not-a-directory",
+    r#"spec_template = "error.spec.in"
+assets = [{ source = "notes.txt/", dest = "/usr/share/error-project/" }]
+"#,
+  );
+
+  let stderr = build_fails_with(&project_dir, &["--dry-run"], 5);
+  assert!(stderr.contains("Asset source 'notes.txt/' is marked as a directory"));
+}
+
+#[test]
+#[serial]
+fn test_missing_asset_source_exits_with_asset_code() {
+  let project_dir = project_with(
+    "missing-source",
+    r#"spec_template = "error.spec.in"
+build_command = "true"
+assets = [{ source = "missing.txt", dest = "/usr/share/error-project/missing.txt" }]
+"#,
+  );
+
+  let stderr = build_fails_with(&project_dir, &["--backend", "native"], 5);
+  assert!(stderr.contains("Asset source file not found"));
+}
+
+#[test]
+#[serial]
+fn test_failed_build_command_exits_with_build_code() {
+  let project_dir = project_with(
+    "build-command",
+    r#"spec_template = "error.spec.in"
+build_command = ["true", "false"]
+"#,
+  );
+
+  let stderr = build_fails_with(&project_dir, &["--backend", "native"], 7);
+  assert!(stderr.contains("Build command `false` failed"));
+}
+
+#[test]
+#[serial]
+fn test_missing_systemd_unit_exits_with_config_code() {
+  let project_dir = project_with(
+    "missing-unit",
+    r#"spec_template = "error.spec.in"
+systemd_units = ["missing.service"]
+"#,
+  );
+
+  let stderr = build_fails_with(&project_dir, &["--dry-run"], 3);
+  assert!(stderr.contains("Failed to read systemd unit at"));
+}
+
+#[test]
+#[serial]
+fn test_missing_scriptlet_exits_with_config_code() {
+  let project_dir = project_with(
+    "missing-scriptlet",
+    r#"spec_template = "error.spec.in"
+scripts = { post = "missing.sh" }
+"#,
+  );
+
+  let stderr = build_fails_with(&project_dir, &["--dry-run"], 3);
+  assert!(stderr.contains("Failed to read scriptlet file at"));
+}
+
+#[test]
+#[serial]
+fn test_unwritable_sysusers_file_exits_with_config_code() {
+  let project_dir = project_with(
+    "sysusers",
+    r#"spec_template = "error.spec.in"
+build_command = "true"
+users = [{ name = "error-project" }]
+"#,
+  );
+  // The generated file goes below `target/revolve-generated`, which is a file here.
+  fs::create_dir_all(project_dir.join("target")).unwrap();
+  fs::write(project_dir.join("target/revolve-generated"), "").unwrap();
+
+  let stderr = build_fails_with(&project_dir, &["--backend", "native"], 3);
+  assert!(stderr.contains("Failed to create directory at"));
+}
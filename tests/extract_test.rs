@@ -0,0 +1,164 @@
+mod common;
+
+use common::create_revolve_command;
+use rpm::{FileMode, FileOptions, PackageBuilder};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+/// Writes a small package to a fresh directory named after the test and returns the directory
+/// and the RPM's path.
+fn write_rpm(test: &str) -> (PathBuf, PathBuf) {
+  let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test);
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+
+  let package = PackageBuilder::new("my-app", "0.1.0", "MIT", "x86_64", "My app")
+    .release("1")
+    .with_file_contents(Vec::new(), FileOptions::new("/opt/my-app").mode(FileMode::dir(0o750)))
+    .unwrap()
+    .with_file_contents("#!/bin/sh\n", FileOptions::new("/opt/my-app/run").mode(0o100755))
+    .unwrap()
+    .with_file_contents("a = 1\n", FileOptions::new("/etc/my-app.conf").mode(0o100600))
+    .unwrap()
+    .with_file_contents(
+      "/opt/my-app/run",
+      FileOptions::new("/usr/bin/my-app")
+        .symlink("/opt/my-app/run")
+        .mode(FileMode::symbolic_link(0o777)),
+    )
+    .unwrap()
+    .build()
+    .unwrap();
+  let rpm = dir.join("my-app-0.1.0-1.x86_64.rpm");
+  package.write_file(&rpm).unwrap();
+  (dir, rpm)
+}
+
+fn mode(path: &Path) -> u32 {
+  fs::metadata(path).unwrap().permissions().mode() & 0o7777
+}
+
+#[test]
+fn test_extract_preserves_modes_and_symlinks() {
+  let (dir, rpm) = write_rpm("extract_all");
+  let dest = dir.join("root");
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd.arg("extract").arg(&rpm).arg("--to").arg(&dest).assert().success();
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains("Extracted 4 file(s)"), "{}", output);
+
+  assert_eq!(fs::read_to_string(dest.join("opt/my-app/run")).unwrap(), "#!/bin/sh\n");
+  assert_eq!(mode(&dest.join("opt/my-app/run")), 0o755);
+  assert_eq!(mode(&dest.join("opt/my-app")), 0o750);
+  assert_eq!(fs::read_to_string(dest.join("etc/my-app.conf")).unwrap(), "a = 1\n");
+  assert_eq!(mode(&dest.join("etc/my-app.conf")), 0o600);
+  assert_eq!(
+    fs::read_link(dest.join("usr/bin/my-app")).unwrap(),
+    Path::new("/opt/my-app/run")
+  );
+}
+
+#[test]
+fn test_extract_selected_files_to_default_directory() {
+  let (dir, rpm) = write_rpm("extract_selected");
+
+  let mut cmd = create_revolve_command();
+  cmd
+    .current_dir(&dir)
+    .arg("extract")
+    .arg(&rpm)
+    .args(["--file", "/opt/my-app"])
+    .assert()
+    .success();
+
+  // The directory is named after the RPM file and only holds the selected directory.
+  let dest = dir.join("my-app-0.1.0-1.x86_64");
+  assert!(dest.join("opt/my-app/run").is_file());
+  assert!(!dest.join("etc").exists());
+  assert!(!dest.join("usr").exists());
+}
+
+#[test]
+fn test_extract_missing_file() {
+  let (dir, rpm) = write_rpm("extract_missing");
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .arg("extract")
+    .arg(&rpm)
+    .arg("--to")
+    .arg(dir.join("root"))
+    .args(["--file", "/etc/other.conf"])
+    .assert()
+    .failure()
+    .code(3);
+  let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+  assert!(stderr.contains("/etc/other.conf is not in"), "{}", stderr);
+  assert!(!dir.join("root").exists());
+}
+
+#[test]
+fn test_extract_package_from_library() {
+  let (dir, rpm) = write_rpm("extract_library");
+  let dest = dir.join("root");
+
+  let files = [PathBuf::from("/etc/my-app.conf")];
+  let extracted = cargo_revolve::extract_package(&rpm, &dest, &files).unwrap();
+  assert_eq!(extracted, [dest.join("etc/my-app.conf")]);
+}
+
+#[test]
+fn test_extract_selected_files_without_leading_slash() {
+  let (dir, rpm) = write_rpm("extract_relative");
+  let dest = dir.join("root");
+
+  let files = [PathBuf::from("usr/bin/my-app"), PathBuf::from("etc")];
+  let mut extracted = cargo_revolve::extract_package(&rpm, &dest, &files).unwrap();
+  extracted.sort();
+  assert_eq!(extracted, [dest.join("etc/my-app.conf"), dest.join("usr/bin/my-app")]);
+}
+
+#[test]
+fn test_extract_never_writes_outside_the_destination() {
+  let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("extract_malicious");
+  let _ = fs::remove_dir_all(&dir);
+  let outside = dir.join("outside");
+  fs::create_dir_all(&outside).unwrap();
+  let dest = dir.join("root");
+
+  // `/a` points out of the destination, and `/a/x` would be written through it.
+  let package = PackageBuilder::new("evil", "0.1.0", "MIT", "x86_64", "Evil")
+    .with_file_contents(
+      outside.to_str().unwrap(),
+      FileOptions::new("/a")
+        .symlink(outside.to_str().unwrap())
+        .mode(FileMode::symbolic_link(0o777)),
+    )
+    .unwrap()
+    .with_file_contents("pwned\n", FileOptions::new("/a/x").mode(0o100644))
+    .unwrap()
+    .build()
+    .unwrap();
+  let evil_rpm = dir.join("evil-0.1.0-1.x86_64.rpm");
+  package.write_file(&evil_rpm).unwrap();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd.arg("extract").arg(&evil_rpm).arg("--to").arg(&dest).assert().failure();
+  let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+  assert!(stderr.contains("through the symlink"), "{}", stderr);
+  assert!(!outside.join("x").exists(), "a file was written outside --to");
+
+  // Links that are already in the destination are not followed either.
+  let (_, rpm) = write_rpm("extract_malicious_existing");
+  let dest = dir.join("existing");
+  fs::create_dir_all(dest.join("opt")).unwrap();
+  std::os::unix::fs::symlink(&outside, dest.join("usr")).unwrap();
+  std::os::unix::fs::symlink(outside.join("victim"), dest.join("opt/my-app")).unwrap();
+  cargo_revolve::extract_package(&rpm, &dest, &[PathBuf::from("/opt")]).unwrap();
+  assert!(dest.join("opt/my-app").is_dir(), "the link at the entry was not replaced");
+  assert!(!outside.join("victim").exists());
+  assert!(cargo_revolve::extract_package(&rpm, &dest, &[PathBuf::from("/usr")]).is_err());
+  assert_eq!(fs::read_dir(&outside).unwrap().count(), 0, "a file was written outside --to");
+}
@@ -0,0 +1,3 @@
+fn main() {
+    println!("broken-unit-project daemon");
+}
@@ -0,0 +1,5 @@
+#!/bin/sh
+# Stop the service before the package is removed.
+if [ "$1" -eq 0 ]; then
+  systemctl stop packaging-project.service >/dev/null 2>&1 || :
+fi
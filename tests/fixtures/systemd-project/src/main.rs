@@ -0,0 +1,3 @@
+fn main() {
+    println!("systemd-project daemon");
+}
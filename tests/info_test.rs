@@ -0,0 +1,135 @@
+mod common;
+
+use common::{create_revolve_command, find_rpm};
+use serde_json::Value;
+use serial_test::serial;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const LIBRARY_FIXTURE_DIR: &str = "tests/fixtures/library-project";
+
+/// Builds the fixture with the native backend and returns the path of its RPM.
+fn build_rpm() -> PathBuf {
+  let fixture_path = Path::new(LIBRARY_FIXTURE_DIR);
+  let _ = fs::remove_dir_all(fixture_path.join("target"));
+  let _ = fs::remove_dir_all(fixture_path.join("dist"));
+
+  let mut cmd = create_revolve_command();
+  cmd
+    .current_dir(LIBRARY_FIXTURE_DIR)
+    .args(["build", "--backend", "native"])
+    .assert()
+    .success();
+
+  fs::canonicalize(find_rpm(&fixture_path.join("dist"))).unwrap()
+}
+
+/// Runs `info` on `rpm` with `args` and returns stdout.
+fn info(rpm: &Path, args: &[&str]) -> String {
+  let mut cmd = create_revolve_command();
+  let assert = cmd.arg("info").arg(rpm).args(args).assert().success();
+  String::from_utf8(assert.get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+#[serial]
+fn test_info_prints_summary_by_default() {
+  let rpm = build_rpm();
+
+  let output = info(&rpm, &[]);
+  assert!(output.contains("  Name:      library-project"));
+  assert!(output.contains("  License:   MIT"));
+  assert!(output.contains("  /etc/library-project/app.toml"));
+}
+
+#[test]
+#[serial]
+fn test_info_queries_one_entry_per_line() {
+  let rpm = build_rpm();
+
+  let requires = info(&rpm, &["--requires"]);
+  let lines: Vec<&str> = requires.lines().collect();
+  assert!(lines.contains(&"ca-certificates"), "{}", requires);
+  assert!(lines.contains(&"openssl-libs >= 3.0"), "{}", requires);
+
+  let provides = info(&rpm, &["--provides"]);
+  assert!(provides.lines().any(|line| line == "library-project = 0.1.0"), "{}", provides);
+
+  let scripts = info(&rpm, &["--scripts"]);
+  assert!(scripts.starts_with("post scriptlet (using /bin/sh):\n"), "{}", scripts);
+  assert!(scripts.contains("echo \"library-project installed\""));
+
+  let changelog = info(&rpm, &["--changelog"]);
+  let entry = "* Mon Jan 01 2024 Jane Doe <jane@example.com> - 0.1.0-1\n- First release.\n";
+  assert!(changelog.starts_with(entry), "{}", changelog);
+
+  let files = info(&rpm, &["--files"]);
+  assert!(files.lines().any(|line| line == "/usr/share/doc/library-project/README.md"));
+}
+
+#[test]
+#[serial]
+fn test_info_long_file_list() {
+  let rpm = build_rpm();
+  let readme_digest = sha256_hex(Path::new(LIBRARY_FIXTURE_DIR).join("README.md"));
+
+  let files = info(&rpm, &["--files", "--long"]);
+  let readme = files
+    .lines()
+    .find(|line| line.ends_with(" /usr/share/doc/library-project/README.md"))
+    .unwrap_or_else(|| panic!("README.md is missing: {}", files));
+  let fields: Vec<&str> = readme.split_whitespace().collect();
+  assert_eq!(fields[..3], ["-rw-r--r--", "root", "root"]);
+  assert_eq!(fields[4], readme_digest);
+
+  let dir = files.lines().find(|line| line.ends_with(" /etc/library-project")).unwrap();
+  assert!(dir.starts_with("drwxr-xr-x"), "{}", dir);
+}
+
+#[test]
+#[serial]
+fn test_info_json_and_yaml() {
+  let rpm = build_rpm();
+
+  let full: Value = serde_json::from_str(&info(&rpm, &["--format", "json"])).unwrap();
+  assert_eq!(full["name"], "library-project");
+  assert_eq!(full["release"], "1");
+  assert_eq!(full["scripts"][0]["name"], "post");
+  let app = full["files"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .find(|file| file["path"] == "/etc/library-project/app.toml")
+    .unwrap();
+  assert_eq!(app["kind"], "file");
+  assert_eq!(app["mode"], "0644");
+  assert_eq!(app["flags"], serde_json::json!(["config", "noreplace"]));
+
+  // A query only prints the selected sections.
+  let query: Value =
+    serde_json::from_str(&info(&rpm, &["--format", "json", "--requires", "--files"])).unwrap();
+  let mut keys: Vec<&String> = query.as_object().unwrap().keys().collect();
+  keys.sort();
+  assert_eq!(keys, ["files", "requires"]);
+  assert!(query["files"].as_array().unwrap().contains(&"/etc/library-project/log.toml".into()));
+  assert!(query["requires"].as_array().unwrap().contains(&serde_json::json!({
+    "name": "openssl-libs",
+    "op": ">=",
+    "version": "3.0",
+  })));
+
+  let yaml = info(&rpm, &["--format", "yaml", "--changelog"]);
+  assert!(yaml.starts_with("changelog:\n"), "{}", yaml);
+  assert!(yaml.contains("author: Jane Doe <jane@example.com> - 0.1.0-1"), "{}", yaml);
+}
+
+#[test]
+fn test_info_long_requires_files() {
+  let mut cmd = create_revolve_command();
+  cmd.args(["info", "missing.rpm", "--long"]).assert().failure().code(2);
+}
+
+fn sha256_hex(path: PathBuf) -> String {
+  format!("{:x}", Sha256::digest(fs::read(path).unwrap()))
+}
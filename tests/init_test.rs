@@ -0,0 +1,126 @@
+mod common;
+
+use common::{copy_dir, create_revolve_command, find_rpm};
+use rpm::Package;
+use serial_test::serial;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const INIT_FIXTURE_DIR: &str = "tests/fixtures/init-project";
+
+/// `init` rewrites the manifest, so every test works on a fresh copy of the fixture.
+fn copy_fixture() -> PathBuf {
+  let project_dir = std::env::temp_dir().join("cargo-revolve-init-project");
+  let _ = fs::remove_dir_all(&project_dir);
+  copy_dir(Path::new(INIT_FIXTURE_DIR), &project_dir);
+  project_dir
+}
+
+#[test]
+#[serial]
+fn test_init_scaffolds_a_buildable_package() {
+  let project_dir = copy_fixture();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd.current_dir(&project_dir).arg("init").assert().success();
+  let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(stdout.contains("systemd unit: packaging/init-project.service"));
+
+  // The existing contents, comments included, are kept as they were.
+  let manifest = fs::read_to_string(project_dir.join("Cargo.toml")).unwrap();
+  let original = fs::read_to_string(Path::new(INIT_FIXTURE_DIR).join("Cargo.toml")).unwrap();
+  for line in original.lines() {
+    assert!(manifest.contains(line), "Lost line from Cargo.toml: {}", line);
+  }
+  assert!(manifest.contains("[package.metadata.revolve]\n"));
+  assert!(!manifest.contains("[package.metadata]\n"));
+  assert!(manifest.contains(r#"spec_template = ".revolve/init-project.spec.in""#));
+  assert!(manifest.contains(
+    r#"{ source = "target/release/init-project", dest = "/usr/bin/init-project", mode = "0755", mkdir = false },"#
+  ));
+  assert!(manifest.contains(
+    r#"{ source = "config/", dest = "/etc/init-project/", kind = "config_noreplace" },"#
+  ));
+  assert!(manifest.contains(r#"kind = "license""#));
+  assert!(manifest.contains(r#"kind = "doc""#));
+  assert!(manifest.contains(r#"systemd_units = ["packaging/init-project.service"]"#));
+
+  let spec = fs::read_to_string(project_dir.join(".revolve/init-project.spec.in")).unwrap();
+  assert!(spec.contains("Source0:        {{ builder.archive_filename }}"));
+  assert!(spec.contains("%setup -q -n {{ builder.archive_root_dir }}"));
+  assert!(spec.contains("\"{{ asset.archive_path }}\""));
+
+  // The generated configuration builds and verifies without further edits.
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(&project_dir)
+    .arg("build")
+    .arg("--backend")
+    .arg("native")
+    .arg("--verify")
+    .assert()
+    .success();
+  let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(stdout.contains("Verification successful."));
+
+  let rpm_path = find_rpm(&project_dir.join("dist"));
+  let package = Package::open(&rpm_path).unwrap();
+  let paths = package.metadata.get_file_paths().unwrap();
+  for expected in [
+    "/usr/bin/init-project",
+    "/etc/init-project/settings.toml",
+    "/usr/lib/systemd/system/init-project.service",
+    "/usr/share/licenses/init-project/LICENSE",
+    "/usr/share/doc/init-project/README.md",
+  ] {
+    assert!(paths.iter().any(|p| p == Path::new(expected)), "Missing {}", expected);
+  }
+  assert!(!paths.iter().any(|p| p == Path::new("/usr/bin")));
+
+  let _ = fs::remove_dir_all(&project_dir);
+}
+
+#[test]
+#[serial]
+fn test_init_no_archive_template_and_existing_config() {
+  let project_dir = copy_fixture();
+
+  let mut cmd = create_revolve_command();
+  cmd.current_dir(&project_dir).arg("init").arg("--no-archive").assert().success();
+
+  let spec = fs::read_to_string(project_dir.join(".revolve/init-project.spec.in")).unwrap();
+  assert!(!spec.contains("Source0:"));
+  assert!(!spec.contains("%setup"));
+  assert!(spec.contains("\"{{ asset.source_path }}\""));
+
+  // The binary is read from the Cargo target directory, wherever it is.
+  let target_dir = project_dir.join("elsewhere");
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(&project_dir)
+    .env("CARGO_TARGET_DIR", &target_dir)
+    .arg("build")
+    .arg("--no-archive")
+    .arg("--dry-run")
+    .assert()
+    .success();
+  let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  let settings = project_dir.join("config/settings.toml");
+  assert!(stdout.contains(&format!(
+    r#""{}" "%{{buildroot}}/etc/init-project/settings.toml""#,
+    settings.display()
+  )));
+  let binary = target_dir.join("release/init-project");
+  assert!(stdout.contains(&format!(
+    r#""{}" "%{{buildroot}}/usr/bin/init-project""#,
+    binary.display()
+  )));
+
+  // A second run must not overwrite the configuration written by the first.
+  let mut cmd = create_revolve_command();
+  let assert = cmd.current_dir(&project_dir).arg("init").assert().failure().code(3);
+  let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+  assert!(stderr.contains("already has a `[package.metadata.revolve]` table"));
+
+  let _ = fs::remove_dir_all(&project_dir);
+}
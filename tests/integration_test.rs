@@ -1,12 +1,17 @@
 mod common;
 
-use common::create_revolve_command;
+use cargo_revolve::cargo_metadata::MetadataCommand;
+use cargo_revolve::config::CompressionFormat;
+use cargo_revolve::{BuildOptions, PackagePlan, RevolveConfig, create_artifact_archive};
+use common::{create_revolve_command, find_rpm};
 use rpm::{FileEntry, Package};
 use serial_test::serial;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::io::Read;
+use std::path::Path;
 
 const FIXTURE_DIR: &str = "tests/fixtures/sample-project";
+const WORKSPACE_FIXTURE_DIR: &str = "tests/fixtures/workspace-project";
 
 /// Helper to set up the test environment.
 fn setup_test() {
@@ -34,7 +39,7 @@ fn test_build_happy_path() {
   let rpm_files: Vec<_> = walkdir::WalkDir::new(&target_dir)
     .into_iter()
     .filter_map(|e| e.ok())
-    .filter(|e| e.path().extension().map_or(false, |ext| ext == "rpm"))
+    .filter(|e| e.path().extension().is_some_and(|ext| ext == "rpm"))
     .collect();
 
   // THE FIX:
@@ -166,7 +171,7 @@ fn test_build_expands_directory_assets_and_copies_to_output_dir() {
 
   // Create a helper closure to make finding specific entries by their path easy.
   let find_entry = |path: &str| -> Option<&FileEntry> {
-    file_entries.iter().find(|e| e.path == PathBuf::from(path))
+    file_entries.iter().find(|e| e.path == Path::new(path))
   };
 
   // 7. VERIFICATION & ASSERTIONS:
@@ -273,3 +278,226 @@ fn test_changelog_in_dry_run() {
   assert!(output.contains("Initial release of the sample project."));
   assert!(output.contains("- This is a test entry."));
 }
+
+#[test]
+#[serial]
+fn test_build_native_backend_verify() {
+  // The native backend writes the RPM itself, so this test does not require rpmbuild.
+  setup_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(FIXTURE_DIR)
+    .arg("build")
+    .arg("--backend")
+    .arg("native")
+    .arg("--verify")
+    .assert()
+    .success();
+
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains("Verification successful."));
+
+  let rpm_path = Path::new(FIXTURE_DIR).join(format!(
+    "dist/sample-project-0.1.0-1.{}.rpm",
+    match std::env::consts::ARCH {
+      "x86" => "i686",
+      "arm" => "armv7hl",
+      "powerpc64" => "ppc64",
+      other => other,
+    }
+  ));
+  let package = Package::open(&rpm_path)
+    .unwrap_or_else(|_| panic!("Failed to open and parse RPM at {}", rpm_path.display()));
+
+  assert_eq!(package.metadata.get_name().unwrap(), "sample-project");
+  assert_eq!(package.metadata.get_license().unwrap(), "MIT");
+
+  let file_entries: Vec<FileEntry> = package.metadata.get_file_entries().unwrap();
+  let binary = file_entries
+    .iter()
+    .find(|e| e.path == Path::new("/usr/bin/sample-project"))
+    .expect("RPM is missing the binary file");
+  assert_eq!(binary.mode.permissions() & 0o7777, 0o755);
+  assert!(
+    file_entries
+      .iter()
+      .any(|e| e.path == Path::new("/etc/sample-project/conf.d/nested/extra.toml")),
+    "RPM is missing the expanded nested config file"
+  );
+}
+
+#[test]
+#[serial]
+fn test_dry_run_with_cross_target() {
+  // A dry run does not compile, so the target does not need to be installed.
+  setup_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(FIXTURE_DIR)
+    .arg("build")
+    .arg("--dry-run")
+    .arg("--target")
+    .arg("aarch64-unknown-linux-gnu")
+    .assert()
+    .success();
+
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains("cargo build"));
+  assert!(output.contains("--target aarch64-unknown-linux-gnu"));
+  assert!(output.contains("rpmbuild -ta"));
+  assert!(output.contains("--target aarch64\n"));
+
+  // The native dry run lists the rewritten asset sources and the mapped RPM architecture.
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(FIXTURE_DIR)
+    .arg("build")
+    .arg("--dry-run")
+    .arg("--backend")
+    .arg("native")
+    .arg("--target")
+    .arg("armv7-unknown-linux-gnueabihf")
+    .assert()
+    .success();
+
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains(
+    "target/armv7-unknown-linux-gnueabihf/release/sample-project -> /usr/bin/sample-project"
+  ));
+  assert!(output.contains("sample.service -> /usr/lib/systemd/system/sample.service"));
+  assert!(output.contains("The armv7hl RPM would be written"));
+}
+
+#[test]
+#[serial]
+fn test_target_triples_map_to_rpm_arches() {
+  setup_test();
+
+  let cases = [
+    ("x86_64-unknown-linux-gnu", "x86_64"),
+    ("aarch64-unknown-linux-musl", "aarch64"),
+    ("armv7-unknown-linux-gnueabihf", "armv7hl"),
+    ("armv7-unknown-linux-musleabihf", "armv7hl"),
+    ("thumbv7neon-unknown-linux-gnueabihf", "armv7hl"),
+    // Soft-float ABIs must not be labelled as hard-float.
+    ("armv7-unknown-linux-gnueabi", "armv7l"),
+    ("armv7-unknown-linux-musleabi", "armv7l"),
+    ("arm-unknown-linux-gnueabihf", "armv6hl"),
+    ("arm-unknown-linux-gnueabi", "armv5tel"),
+    ("powerpc64le-unknown-linux-gnu", "ppc64le"),
+    ("riscv64gc-unknown-linux-gnu", "riscv64"),
+  ];
+  for (triple, arch) in cases {
+    let mut cmd = create_revolve_command();
+    let assert = cmd
+      .current_dir(FIXTURE_DIR)
+      .args(["build", "--dry-run", "--backend", "native", "--target", triple])
+      .assert()
+      .success();
+    let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    let expected = format!("The {} RPM would be written", arch);
+    assert!(output.contains(&expected), "{} should map to {}:\n{}", triple, arch, output);
+  }
+}
+
+#[test]
+#[serial]
+fn test_unknown_target_triple_is_rejected() {
+  setup_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(FIXTURE_DIR)
+    .arg("build")
+    .arg("--dry-run")
+    .arg("--target")
+    .arg("wasm32-unknown-unknown")
+    .assert()
+    .failure()
+    .code(3);
+
+  let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+  assert!(stderr.contains("Cannot map target triple `wasm32-unknown-unknown` to an RPM architecture"));
+}
+
+/// Lists the entries of an uncompressed tar archive.
+fn tar_entries(tarball: &[u8]) -> Vec<String> {
+  tar::Archive::new(tarball)
+    .entries()
+    .unwrap()
+    .map(|e| e.unwrap().path().unwrap().to_string_lossy().into_owned())
+    .collect()
+}
+
+#[test]
+#[serial]
+fn test_configured_archive_and_payload_compression() {
+  let workspace_path = Path::new(WORKSPACE_FIXTURE_DIR);
+  let _ = fs::remove_dir_all(workspace_path.join("target"));
+  let _ = fs::remove_dir_all(workspace_path.join("service-b/target"));
+  let _ = fs::remove_dir_all(workspace_path.join("service-b/dist"));
+
+  // service-b uses a zstd archive at level 19 and an xz payload at level 6.
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(WORKSPACE_FIXTURE_DIR)
+    .args(["build", "-p", "service-b", "--dry-run"])
+    .assert()
+    .success();
+  let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(stdout.contains("Source0:        service-b-0.1.0.tar.zst"));
+  assert!(stdout.contains("/target/service-b-0.1.0.tar.zst --specfile"));
+  assert!(stdout.contains("--define='_binary_payload w6.xzdio'"));
+
+  let mut cmd = create_revolve_command();
+  cmd
+    .current_dir(WORKSPACE_FIXTURE_DIR)
+    .args(["build", "-p", "service-b", "--backend", "native"])
+    .assert()
+    .success();
+  let rpm_path = find_rpm(&workspace_path.join("service-b/dist"));
+  let package = Package::open(&rpm_path).unwrap();
+  assert_eq!(package.metadata.get_payload_compressor().unwrap(), rpm::CompressionType::Xz);
+
+  // Write the source archive, now that the binary is built.
+  let metadata = MetadataCommand::new()
+    .manifest_path(workspace_path.join("Cargo.toml"))
+    .no_deps()
+    .exec()
+    .unwrap();
+  let package = metadata.packages.iter().find(|p| p.name.as_str() == "service-b").unwrap();
+  let target_dir = metadata.target_directory.as_std_path();
+  let mut config = RevolveConfig::from_manifest(package.manifest_path.as_std_path()).unwrap();
+  let write_archive = |config: &RevolveConfig| {
+    let plan = PackagePlan::new(config, package, target_dir, &BuildOptions::new()).unwrap();
+    create_artifact_archive(&plan, false).unwrap()
+  };
+
+  let archive_path = write_archive(&config);
+  assert!(archive_path.ends_with("service-b/target/service-b-0.1.0.tar.zst"));
+  let compressed = fs::read(&archive_path).unwrap();
+  let tarball = zstd::decode_all(compressed.as_slice()).unwrap();
+  assert_eq!(tar_entries(&tarball), ["service-b-0.1.0/target/release/service-b"]);
+  // zstd does not record the level, but compressing the same tarball at level 19 must give the
+  // same bytes.
+  assert!(zstd::encode_all(tarball.as_slice(), 19).unwrap() == compressed);
+
+  // Other codecs hold the very same tarball.
+  config.archive_compression = Some(CompressionFormat::Bzip2);
+  config.archive_compression_level = Some(1);
+  let archive_path = write_archive(&config);
+  assert!(archive_path.ends_with("service-b/target/service-b-0.1.0.tar.bz2"));
+  let mut bzip2_tarball = Vec::new();
+  bzip2::read::BzDecoder::new(fs::File::open(&archive_path).unwrap())
+    .read_to_end(&mut bzip2_tarball)
+    .unwrap();
+  assert!(bzip2_tarball == tarball, "the bzip2 archive holds a different tarball");
+
+  config.archive_compression = Some(CompressionFormat::None);
+  config.archive_compression_level = None;
+  let archive_path = write_archive(&config);
+  assert!(archive_path.ends_with("service-b/target/service-b-0.1.0.tar"));
+  assert!(fs::read(&archive_path).unwrap() == tarball, "the uncompressed archive differs");
+}
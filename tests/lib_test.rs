@@ -0,0 +1,303 @@
+use cargo_revolve::cargo_metadata::{Metadata, MetadataCommand};
+use cargo_revolve::config::BuildCommand;
+use cargo_revolve::{
+  Backend, BuildOptions, PackagePlan, PackageSelection, RevolveConfig, RevolveError, RpmInfo,
+  create_artifact_archive, expand_assets, render_spec_content, verify_package,
+};
+use serial_test::serial;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const LIBRARY_FIXTURE_DIR: &str = "tests/fixtures/library-project";
+const SAMPLE_FIXTURE_DIR: &str = "tests/fixtures/sample-project";
+
+/// Cleans up previous runs and reads the metadata of the fixture.
+fn setup_test() -> Metadata {
+  let fixture_path = Path::new(LIBRARY_FIXTURE_DIR);
+  let _ = fs::remove_dir_all(fixture_path.join("target"));
+  let _ = fs::remove_dir_all(fixture_path.join("dist"));
+
+  MetadataCommand::new()
+    .manifest_path(fixture_path.join("Cargo.toml"))
+    .no_deps()
+    .exec()
+    .unwrap()
+}
+
+fn load_config(metadata: &Metadata) -> RevolveConfig {
+  let package = metadata.root_package().unwrap();
+  RevolveConfig::from_manifest(package.manifest_path.as_std_path()).unwrap()
+}
+
+#[test]
+fn test_from_manifest_reads_revolve_table() {
+  let config =
+    RevolveConfig::from_manifest(&Path::new(LIBRARY_FIXTURE_DIR).join("Cargo.toml")).unwrap();
+
+  assert_eq!(config.spec_template, "library.spec.in");
+  assert_eq!(config.assets.as_ref().map(Vec::len), Some(2));
+}
+
+#[test]
+fn test_from_manifest_without_revolve_table() {
+  let error = RevolveConfig::from_manifest(Path::new("tests/fixtures/error-project/Cargo.toml"))
+    .unwrap_err();
+
+  assert!(matches!(error, RevolveError::Config(_)), "unexpected error: {:?}", error);
+  assert_eq!(error.exit_code(), 3);
+}
+
+#[test]
+#[serial]
+fn test_plan_expands_every_file() {
+  let metadata = setup_test();
+  let package = metadata.root_package().unwrap();
+  let config = load_config(&metadata);
+  let target_dir = metadata.target_directory.as_std_path();
+
+  let plan = PackagePlan::new(&config, package, target_dir, &BuildOptions::new()).unwrap();
+
+  let assets = plan.config().assets.as_deref().unwrap();
+  let mut dests: Vec<&str> = assets.iter().map(|asset| asset.dest.as_str()).collect();
+  dests.sort();
+  assert_eq!(
+    dests,
+    [
+      "/etc/library-project/app.toml",
+      "/etc/library-project/log.toml",
+      "/usr/share/doc/library-project/README.md",
+    ]
+  );
+  assert_eq!(plan.created_dirs(), ["/etc/library-project/", "/usr/share/doc/library-project"]);
+}
+
+#[test]
+#[serial]
+fn test_expand_assets_without_a_plan() {
+  let metadata = setup_test();
+  let config = load_config(&metadata);
+  let target_dir = metadata.target_directory.as_std_path();
+
+  let (assets, dirs) =
+    expand_assets(config.assets.as_deref().unwrap(), Path::new(LIBRARY_FIXTURE_DIR), target_dir)
+      .unwrap();
+  let mut sources: Vec<&str> = assets.iter().map(|asset| asset.source.as_str()).collect();
+  sources.sort();
+  assert_eq!(sources, ["README.md", "config/app.toml", "config/log.toml"]);
+  assert_eq!(dirs, ["/etc/library-project/", "/usr/share/doc/library-project"]);
+
+  let error = expand_assets(
+    config.assets.as_deref().unwrap(),
+    Path::new("tests/fixtures/missing-project"),
+    target_dir,
+  )
+  .unwrap_err();
+  assert!(matches!(error, RevolveError::NotADirectory(_)), "{:?}", error);
+  assert_eq!(error.exit_code(), 5);
+}
+
+#[test]
+#[serial]
+fn test_plan_renders_spec_and_archive() {
+  let metadata = setup_test();
+  let package = metadata.root_package().unwrap();
+  let config = load_config(&metadata);
+  let target_dir = metadata.target_directory.as_std_path();
+
+  let plan = PackagePlan::new(&config, package, target_dir, &BuildOptions::new()).unwrap();
+  assert_eq!(plan.package().name.as_str(), "library-project");
+  assert_eq!(plan.config().assets.as_ref().map(Vec::len), Some(3));
+
+  let spec = render_spec_content(&plan).unwrap();
+  assert!(spec.contains("Name:           library-project"));
+  assert!(spec.contains("\"config/app.toml\" \"%{buildroot}/etc/library-project/app.toml\""));
+
+  let archive_path = create_artifact_archive(&plan, false).unwrap();
+  assert!(archive_path.ends_with("library-project/target/library-project-0.1.0.tar.gz"));
+  assert!(archive_path.exists(), "the archive was not written");
+}
+
+#[test]
+#[serial]
+fn test_build_verify_and_read_with_native_backend() {
+  let metadata = setup_test();
+  let package = metadata.root_package().unwrap();
+  let config = load_config(&metadata);
+  let target_dir = metadata.target_directory.as_std_path();
+
+  let options = BuildOptions::new().backend(Backend::Native).verify(true);
+  let report = cargo_revolve::build(&config, package, target_dir, &options).unwrap();
+  assert_eq!(report.release.as_deref(), Some("1"));
+  assert_eq!(report.commands.len(), 1);
+  assert_eq!(report.commands[0].command, "true");
+  let verification = report.verification.as_deref().unwrap();
+  assert!(verification.iter().all(|v| v.passed), "{:?}", verification);
+
+  assert_eq!(report.artifacts.len(), 1, "unexpected artifacts: {:?}", report.artifacts);
+  let rpm_path = &report.artifacts[0].path;
+  assert!(rpm_path.parent().unwrap().ends_with("library-project/dist"), "{:?}", rpm_path);
+  assert_eq!(report.artifacts[0].size, fs::metadata(rpm_path).unwrap().len());
+
+  // The RPM can be checked again later from a fresh plan.
+  let plan = PackagePlan::new(&config, package, target_dir, &BuildOptions::new()).unwrap();
+  verify_package(&plan, rpm_path).unwrap();
+
+  let info = RpmInfo::read(rpm_path).unwrap();
+  assert_eq!(info.name, "library-project");
+  assert_eq!(info.version, "0.1.0");
+  assert_eq!(info.license.as_deref(), Some("MIT"));
+  let paths: Vec<&Path> = info.files.iter().map(|file| file.path.as_path()).collect();
+  assert!(paths.contains(&Path::new("/etc/library-project/app.toml")));
+  assert!(paths.contains(&Path::new("/usr/share/doc/library-project/README.md")));
+}
+
+#[test]
+#[serial]
+fn test_build_workspace_returns_every_report() {
+  let metadata = setup_test();
+  let options = BuildOptions::new().backend(Backend::Native).dry_run(true);
+
+  let selection = PackageSelection::Packages(vec!["library-project".to_string()]);
+  let build = cargo_revolve::build_workspace(&metadata, &selection, &options).unwrap();
+  build.result.unwrap();
+  assert_eq!(build.reports.len(), 1);
+  assert_eq!(build.reports[0].name, "library-project");
+  assert!(build.reports[0].dry_run);
+
+  let selection = PackageSelection::Packages(vec!["not-a-member".to_string()]);
+  let error = cargo_revolve::build_workspace(&metadata, &selection, &options).err().unwrap();
+  assert!(matches!(error, RevolveError::Config(_)), "{:?}", error);
+}
+
+#[test]
+#[serial]
+fn test_verify_rejects_a_file_that_is_not_an_rpm() {
+  let metadata = setup_test();
+  let package = metadata.root_package().unwrap();
+  let config = load_config(&metadata);
+  let target_dir = metadata.target_directory.as_std_path();
+
+  let plan = PackagePlan::new(&config, package, target_dir, &BuildOptions::new()).unwrap();
+  fs::create_dir_all(target_dir).unwrap();
+  let not_an_rpm = target_dir.join("library-project-0.1.0-1.noarch.rpm");
+  fs::write(&not_an_rpm, "not an RPM").unwrap();
+
+  let error = verify_package(&plan, &not_an_rpm).unwrap_err();
+  assert!(matches!(error, RevolveError::Verification(_)), "{:?}", error);
+  assert_eq!(error.exit_code(), 9);
+}
+
+#[test]
+#[serial]
+fn test_verify_compares_contents_with_sources() {
+  let fixture_path = Path::new(SAMPLE_FIXTURE_DIR);
+  let _ = fs::remove_dir_all(fixture_path.join("dist"));
+  let metadata = MetadataCommand::new()
+    .manifest_path(fixture_path.join("Cargo.toml"))
+    .exec()
+    .unwrap();
+  let package = metadata.root_package().unwrap();
+  let config = load_config(&metadata);
+  let target_dir = metadata.target_directory.as_std_path();
+
+  let options = BuildOptions::new().backend(Backend::Native);
+  let report = cargo_revolve::build(&config, package, target_dir, &options).unwrap();
+  let rpm_path = &report.artifacts[0].path;
+  let plan = PackagePlan::new(&config, package, target_dir, &BuildOptions::new()).unwrap();
+  verify_package(&plan, rpm_path).unwrap();
+
+  // Stripping changes the digest of the binary, but not its build ID. Only rpmbuild strips
+  // binaries, so a plan for the native backend still rejects the stripped copy.
+  let binary = target_dir.join("release/sample-project");
+  let unstripped = fs::read(&binary).unwrap();
+  if Command::new("strip").arg(&binary).status().is_ok_and(|status| status.success()) {
+    verify_package(&plan, rpm_path).unwrap();
+    // A binary that was already stripped is left as it is.
+    if fs::read(&binary).unwrap() != unstripped {
+      let native_plan = PackagePlan::new(&config, package, target_dir, &options).unwrap();
+      let error = verify_package(&native_plan, rpm_path).unwrap_err();
+      assert_eq!(error.exit_code(), 9);
+    }
+  }
+
+  // A binary from another build is reported. Cargo hard-links the binary into `deps/`, so the
+  // link is replaced instead of written through.
+  fs::remove_file(&binary).unwrap();
+  fs::write(&binary, "a stale binary").unwrap();
+  let error = verify_package(&plan, rpm_path).unwrap_err();
+  assert!(matches!(error, RevolveError::Verification(_)), "{:?}", error);
+  assert_eq!(error.exit_code(), 9);
+  let _ = fs::remove_file(&binary);
+}
+
+#[test]
+#[serial]
+fn test_verify_without_build_ids() {
+  if which::which("objcopy").is_err() {
+    println!("SKIPPING TEST: `objcopy` command not found in PATH.");
+    return;
+  }
+  let fixture_path = Path::new(SAMPLE_FIXTURE_DIR);
+  let _ = fs::remove_dir_all(fixture_path.join("dist"));
+  let metadata = MetadataCommand::new()
+    .manifest_path(fixture_path.join("Cargo.toml"))
+    .exec()
+    .unwrap();
+  let package = metadata.root_package().unwrap();
+  let mut config = load_config(&metadata);
+  let target_dir = metadata.target_directory.as_std_path();
+
+  let options = BuildOptions::new().backend(Backend::Native);
+  let report = cargo_revolve::build(&config, package, target_dir, &options).unwrap();
+  let rpm_path = report.artifacts[0].path.clone();
+  let plan = PackagePlan::new(&config, package, target_dir, &BuildOptions::new()).unwrap();
+
+  // Without a build ID in the source, a different binary in the package may be a stale one.
+  // The hard link into `deps/` is replaced instead of written through.
+  let binary = target_dir.join("release/sample-project");
+  let original = fs::read(&binary).unwrap();
+  let without_build_id = target_dir.join("sample-project-without-build-id");
+  let status = Command::new("objcopy")
+    .args(["--remove-section", ".note.gnu.build-id"])
+    .arg(&binary)
+    .arg(&without_build_id)
+    .status()
+    .unwrap();
+  assert!(status.success());
+  fs::remove_file(&binary).unwrap();
+  fs::copy(&without_build_id, &binary).unwrap();
+  let error = verify_package(&plan, &rpm_path).unwrap_err();
+  assert!(matches!(error, RevolveError::Verification(_)), "{:?}", error);
+  assert_eq!(error.exit_code(), 9);
+
+  // A stale binary without a build ID in the package. Packaging what is on disk skips the
+  // compilation.
+  config.build_command = Some(BuildCommand::Single("true".to_string()));
+  let report = cargo_revolve::build(&config, package, target_dir, &options).unwrap();
+  let stale_rpm_path = report.artifacts[0].path.clone();
+  fs::remove_file(&binary).unwrap();
+  fs::write(&binary, &original).unwrap();
+  let error = verify_package(&plan, &stale_rpm_path).unwrap_err();
+  assert_eq!(error.exit_code(), 9);
+
+  // Neither copy has a build ID: a rebuilt binary is not taken for a stripped copy.
+  let rebuilt = target_dir.join("sample-project-rebuilt");
+  let extra_section = format!(".rebuilt={}", fixture_path.join("Cargo.toml").display());
+  let status = Command::new("objcopy")
+    .args(["--remove-section", ".note.gnu.build-id", "--add-section", &extra_section])
+    .arg(&binary)
+    .arg(&rebuilt)
+    .status()
+    .unwrap();
+  assert!(status.success());
+  fs::remove_file(&binary).unwrap();
+  fs::copy(&rebuilt, &binary).unwrap();
+  let error = verify_package(&plan, &stale_rpm_path).unwrap_err();
+  assert_eq!(error.exit_code(), 9);
+
+  fs::remove_file(&binary).unwrap();
+  fs::write(&binary, &original).unwrap();
+  let _ = fs::remove_file(&without_build_id);
+  let _ = fs::remove_file(&rebuilt);
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::create_revolve_command;
+use serial_test::serial;
+
+const LINT_FIXTURE_DIR: &str = "tests/fixtures/lint-project";
+const SAMPLE_FIXTURE_DIR: &str = "tests/fixtures/sample-project";
+
+#[test]
+#[serial]
+fn test_lint_reports_config_and_spec_problems() {
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(LINT_FIXTURE_DIR)
+    .arg("lint")
+    .arg("--no-archive")
+    .assert()
+    .failure()
+    .code(10);
+
+  let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+
+  for expected in [
+    "error[mode]: Asset '/etc/lint-project/lint.toml' has mode '0699'",
+    "error[dest]: Asset destination 'usr/share/doc/lint-project/missing.md' is not an absolute",
+    "error[source]: Asset source 'docs/missing.md' does not exist.",
+    "warning[mkdir]: Asset '/opt/lint-project/extra.toml' sets `mkdir = false`",
+    "error[variables]: The template uses `builder.post_script`, which is not defined.",
+    "warning[variables]: `requires` is configured, but the template never uses `builder.requires`",
+    "error[tags]: The `License:` tag is empty.",
+    "error[setup]: The spec uses `%setup`, but `--no-archive` builds have no source archive",
+    "error[files]: Asset destination '/opt/lint-project/extra.toml' is not listed in the `%files`",
+  ] {
+    assert!(stdout.contains(expected), "Missing '{}' in:\n{}", expected, stdout);
+  }
+  // `%{_sysconfdir}/lint-project/` covers the config file below it.
+  assert!(!stdout.contains("'/etc/lint-project/lint.toml' is not listed"));
+  // A binary that has not been built yet is not an error.
+  assert!(stdout.contains("warning[source]: Asset source 'target/release/lint-project'"));
+  assert!(stderr.contains("Lint found 7 error(s) in lint-project."));
+}
+
+#[test]
+#[serial]
+fn test_lint_json_format() {
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(LINT_FIXTURE_DIR)
+    .arg("lint")
+    .arg("--format")
+    .arg("json")
+    .assert()
+    .failure();
+
+  let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  let report: serde_json::Value = serde_json::from_str(&stdout).expect("lint output is not JSON");
+  assert_eq!(report["package"], "lint-project");
+  assert_eq!(report["version"], "0.2.0");
+  // Without `--no-archive`, the `%setup` in the spec is fine.
+  assert_eq!(report["errors"], 6);
+
+  let issues = report["issues"].as_array().unwrap();
+  let counted = report["errors"].as_u64().unwrap() + report["warnings"].as_u64().unwrap();
+  assert_eq!(issues.len() as u64, counted);
+  let mode = issues.iter().find(|issue| issue["check"] == "mode").unwrap();
+  assert_eq!(mode["severity"], "error");
+  assert!(mode["message"].as_str().unwrap().contains("'0699'"));
+  assert!(!issues.iter().any(|issue| issue["check"] == "setup"));
+}
+
+#[test]
+#[serial]
+fn test_lint_passes_for_valid_project() {
+  let mut cmd = create_revolve_command();
+  let assert = cmd.current_dir(SAMPLE_FIXTURE_DIR).arg("lint").assert().success();
+
+  let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(stdout.contains("Linting sample-project 0.1.0..."));
+  assert!(!stdout.contains("error["));
+}
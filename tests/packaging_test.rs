@@ -0,0 +1,310 @@
+mod common;
+
+use cargo_revolve::cargo_metadata::MetadataCommand;
+use cargo_revolve::{BuildOptions, PackagePlan, RevolveConfig, create_artifact_archive};
+use common::{create_revolve_command, find_rpm};
+use rpm::{DependencyFlags, FileEntry, FileFlags, Package};
+use serial_test::serial;
+use std::fs;
+use std::path::Path;
+
+const PACKAGING_FIXTURE_DIR: &str = "tests/fixtures/packaging-project";
+
+fn setup_packaging_test() {
+  let fixture_path = Path::new(PACKAGING_FIXTURE_DIR);
+  let _ = fs::remove_dir_all(fixture_path.join("target"));
+  let _ = fs::remove_dir_all(fixture_path.join("dist"));
+}
+
+#[test]
+#[serial]
+fn test_dependencies_are_rendered_and_recorded() {
+  setup_packaging_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(PACKAGING_FIXTURE_DIR)
+    .arg("build")
+    .arg("--dry-run")
+    .assert()
+    .success();
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains("BuildRequires:  openssl-devel >= 3.0"));
+  assert!(output.contains("Requires:       openssl-libs >= 3.0"));
+  assert!(output.contains("Requires:       ca-certificates"));
+  assert!(output.contains("Provides:       packaging-daemon = 0.1.0"));
+  assert!(output.contains("Conflicts:      legacy-packaging < 0.1"));
+  assert!(output.contains("Obsoletes:      packaging-project-old"));
+
+  // `--verify` checks the dependency headers; inspect them directly as well.
+  let mut cmd = create_revolve_command();
+  cmd
+    .current_dir(PACKAGING_FIXTURE_DIR)
+    .args(["build", "--backend", "native", "--verify"])
+    .assert()
+    .success();
+
+  let rpm_path = find_rpm(&Path::new(PACKAGING_FIXTURE_DIR).join("dist"));
+  let metadata = Package::open(&rpm_path).unwrap().metadata;
+
+  let requires = metadata.get_requires().unwrap();
+  let openssl = requires
+    .iter()
+    .find(|d| d.name == "openssl-libs")
+    .expect("RPM is missing the openssl-libs requirement");
+  assert_eq!(openssl.version, "3.0");
+  assert!(openssl.flags.contains(DependencyFlags::GREATER | DependencyFlags::EQUAL));
+  assert!(requires.iter().any(|d| d.name == "ca-certificates"));
+
+  let provides = metadata.get_provides().unwrap();
+  assert!(provides.iter().any(|d| d.name == "packaging-daemon" && d.version == "0.1.0"));
+  let conflicts = metadata.get_conflicts().unwrap();
+  let legacy = conflicts.iter().find(|d| d.name == "legacy-packaging").unwrap();
+  assert!(legacy.flags.contains(DependencyFlags::LESS));
+  let obsoletes = metadata.get_obsoletes().unwrap();
+  assert!(obsoletes.iter().any(|d| d.name == "packaging-project-old"));
+}
+
+#[test]
+#[serial]
+fn test_auto_requires_from_elf_binaries() {
+  // The native backend records the detected libraries itself, so this needs no rpmbuild.
+  setup_packaging_test();
+
+  let mut cmd = create_revolve_command();
+  cmd
+    .current_dir(PACKAGING_FIXTURE_DIR)
+    .args(["build", "--backend", "native", "--verify"])
+    .assert()
+    .success();
+
+  let rpm_path = find_rpm(&Path::new(PACKAGING_FIXTURE_DIR).join("dist"));
+  let requires = Package::open(&rpm_path).unwrap().metadata.get_requires().unwrap();
+  let names: Vec<&str> = requires.iter().map(|d| d.name.as_str()).collect();
+
+  // Every glibc-linked binary needs libc; the fixture excludes libgcc_s.
+  let libc = if cfg!(target_pointer_width = "64") { "libc.so.6()(64bit)" } else { "libc.so.6" };
+  if cfg!(all(target_os = "linux", target_env = "gnu")) {
+    assert!(names.contains(&libc), "Expected {} in {:?}", libc, names);
+  }
+  assert!(
+    !names.iter().any(|name| name.starts_with("libgcc_s.so")),
+    "Excluded soname was recorded: {:?}",
+    names
+  );
+  // Declared requirements are kept alongside the detected ones.
+  assert!(names.contains(&"openssl-libs"));
+}
+
+#[test]
+#[serial]
+fn test_scriptlets_from_files() {
+  setup_packaging_test();
+  let post = fs::read_to_string(Path::new(PACKAGING_FIXTURE_DIR).join("packaging/post.sh")).unwrap();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(PACKAGING_FIXTURE_DIR)
+    .arg("build")
+    .arg("--dry-run")
+    .assert()
+    .success();
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains(&format!("%post\n{}", post.trim())));
+  assert!(output.contains("%preun\n#!/bin/sh"));
+
+  // `--verify` compares the scriptlets with the files; inspect the header directly as well.
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(PACKAGING_FIXTURE_DIR)
+    .args(["build", "--backend", "native", "--verify"])
+    .assert()
+    .success();
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains("Verification successful."));
+
+  let rpm_path = find_rpm(&Path::new(PACKAGING_FIXTURE_DIR).join("dist"));
+  let metadata = Package::open(&rpm_path).unwrap().metadata;
+  assert_eq!(metadata.get_post_install_script().unwrap().script, post);
+  assert!(metadata.get_pre_uninstall_script().unwrap().script.contains("systemctl stop"));
+  assert!(metadata.get_pre_install_script().is_err());
+}
+
+#[test]
+#[serial]
+fn test_asset_kinds_and_ownership_are_recorded() {
+  setup_packaging_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(PACKAGING_FIXTURE_DIR)
+    .arg("build")
+    .arg("--dry-run")
+    .assert()
+    .success();
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains("%config(noreplace) /etc/packaging-project/conf.d/nested/extra.toml"));
+  assert!(output.contains("%ghost %attr(0640, -, adm) /var/log/packaging-project/packaging.log"));
+  assert!(output.contains("%dir %attr(0750, packaging, packaging) /var/lib/packaging-project"));
+
+  // `--verify` compares the flags with the configured kinds; inspect them directly as well.
+  let mut cmd = create_revolve_command();
+  cmd
+    .current_dir(PACKAGING_FIXTURE_DIR)
+    .args(["build", "--backend", "native", "--verify"])
+    .assert()
+    .success();
+
+  let rpm_path = find_rpm(&Path::new(PACKAGING_FIXTURE_DIR).join("dist"));
+  let file_entries = Package::open(&rpm_path).unwrap().metadata.get_file_entries().unwrap();
+  let find_entry = |path: &str| -> &FileEntry {
+    file_entries
+      .iter()
+      .find(|e| e.path == Path::new(path))
+      .unwrap_or_else(|| panic!("RPM is missing {}", path))
+  };
+
+  // Directory children inherit the kind of their directory asset.
+  let nested = find_entry("/etc/packaging-project/conf.d/nested/extra.toml");
+  assert!(nested.flags.contains(FileFlags::CONFIG | FileFlags::NOREPLACE));
+  let ghost = find_entry("/var/log/packaging-project/packaging.log");
+  assert!(ghost.flags.contains(FileFlags::GHOST));
+  assert_eq!(ghost.mode.permissions() & 0o7777, 0o640);
+  let data_dir = find_entry("/var/lib/packaging-project");
+  assert!(matches!(data_dir.mode, rpm::FileMode::Dir { .. }));
+  assert_eq!(data_dir.mode.permissions() & 0o7777, 0o750);
+  // Ownership defaults to root for anything that does not set it.
+  assert_eq!(data_dir.ownership.user, "packaging");
+  assert_eq!(data_dir.ownership.group, "packaging");
+  assert_eq!(ghost.ownership.user, "root");
+  assert_eq!(ghost.ownership.group, "adm");
+  let binary = find_entry("/usr/bin/packaging-project");
+  assert!(!binary.flags.contains(FileFlags::CONFIG));
+}
+
+#[test]
+#[serial]
+fn test_symlink_assets() {
+  setup_packaging_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(PACKAGING_FIXTURE_DIR)
+    .arg("build")
+    .arg("--dry-run")
+    .assert()
+    .success();
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains("ln -s \"packaging-project\" \"%{buildroot}/usr/bin/packaging\""));
+  assert!(output.contains(
+    "ln -s \"default.css\" \"%{buildroot}/usr/share/packaging-project/themes/current.css\""
+  ));
+
+  let mut cmd = create_revolve_command();
+  cmd
+    .current_dir(PACKAGING_FIXTURE_DIR)
+    .args(["build", "--backend", "native", "--verify"])
+    .assert()
+    .success();
+
+  let rpm_path = find_rpm(&Path::new(PACKAGING_FIXTURE_DIR).join("dist"));
+  let file_entries = Package::open(&rpm_path).unwrap().metadata.get_file_entries().unwrap();
+  let find_entry = |path: &str| -> &FileEntry {
+    file_entries
+      .iter()
+      .find(|e| e.path == Path::new(path))
+      .unwrap_or_else(|| panic!("RPM is missing {}", path))
+  };
+
+  let link = find_entry("/usr/bin/packaging");
+  assert!(matches!(link.mode, rpm::FileMode::SymbolicLink { .. }));
+  assert_eq!(link.linkto, "packaging-project");
+  // With `preserve_symlinks`, links under a directory asset stay links.
+  let preserved = find_entry("/usr/share/packaging-project/themes/current.css");
+  assert!(matches!(preserved.mode, rpm::FileMode::SymbolicLink { .. }));
+  assert_eq!(preserved.linkto, "default.css");
+  let regular = find_entry("/usr/share/packaging-project/themes/default.css");
+  assert!(matches!(regular.mode, rpm::FileMode::Regular { .. }));
+}
+
+#[test]
+#[serial]
+fn test_glob_sources_and_excluded_files() {
+  setup_packaging_test();
+  // An editor backup (excluded by pattern) and a git-ignored local override.
+  let config_dir = Path::new(PACKAGING_FIXTURE_DIR).join("config");
+  fs::write(config_dir.join("app.toml.bak"), "port = 1").unwrap();
+  fs::write(config_dir.join("local.toml"), "debug = true").unwrap();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(PACKAGING_FIXTURE_DIR)
+    .args(["build", "--dry-run", "--backend", "native"])
+    .assert();
+  let _ = fs::remove_file(config_dir.join("app.toml.bak"));
+  let _ = fs::remove_file(config_dir.join("local.toml"));
+  let output = String::from_utf8(assert.success().get_output().stdout.clone()).unwrap();
+
+  assert!(output.contains("docs/README.md -> /usr/share/doc/packaging-project/README.md"));
+  assert!(output.contains("docs/guide/usage.md -> /usr/share/doc/packaging-project/guide/usage.md"));
+  assert!(output.contains("%dir /usr/share/doc/packaging-project/guide\n"));
+  assert!(!output.contains("draft.txt"));
+  assert!(output.contains("config/app.toml -> /etc/packaging-project/conf.d/app.toml"));
+  assert!(!output.contains("app.toml.bak"));
+  assert!(!output.contains("local.toml"));
+  assert!(!output.contains(".gitignore"));
+}
+
+#[test]
+#[serial]
+fn test_archive_preserves_source_layout() {
+  setup_packaging_test();
+
+  // `config/app.toml` and `config/nested/app.toml` share a file name but not an archive path.
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(PACKAGING_FIXTURE_DIR)
+    .arg("build")
+    .arg("--dry-run")
+    .assert()
+    .success();
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains(
+    "\"config/app.toml\" \"%{buildroot}/etc/packaging-project/conf.d/app.toml\""
+  ));
+  assert!(output.contains(
+    "\"config/nested/app.toml\" \"%{buildroot}/etc/packaging-project/conf.d/nested/app.toml\""
+  ));
+  assert!(output.contains(
+    "\"target/release/packaging-project\" \"%{buildroot}/usr/bin/packaging-project\""
+  ));
+
+  // Build the binary, then write the archive that `rpmbuild` would unpack.
+  let mut cmd = create_revolve_command();
+  cmd
+    .current_dir(PACKAGING_FIXTURE_DIR)
+    .args(["build", "--backend", "native"])
+    .assert()
+    .success();
+  let fixture_path = Path::new(PACKAGING_FIXTURE_DIR);
+  let metadata = MetadataCommand::new()
+    .manifest_path(fixture_path.join("Cargo.toml"))
+    .no_deps()
+    .exec()
+    .unwrap();
+  let package = metadata.root_package().unwrap();
+  let config = RevolveConfig::from_manifest(package.manifest_path.as_std_path()).unwrap();
+  let target_dir = metadata.target_directory.as_std_path();
+  let plan = PackagePlan::new(&config, package, target_dir, &BuildOptions::new()).unwrap();
+  let archive_path = create_artifact_archive(&plan, false).unwrap();
+
+  let archive = fs::File::open(archive_path).unwrap();
+  let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(archive));
+  let entries: Vec<String> = archive
+    .entries()
+    .unwrap()
+    .map(|e| e.unwrap().path().unwrap().to_string_lossy().into_owned())
+    .collect();
+  assert!(entries.contains(&"packaging-project-0.1.0/config/app.toml".to_string()));
+  assert!(entries.contains(&"packaging-project-0.1.0/config/nested/app.toml".to_string()));
+}
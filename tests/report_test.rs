@@ -0,0 +1,155 @@
+mod common;
+
+use common::{copy_dir, create_revolve_command};
+use serde_json::Value;
+use serial_test::serial;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+const LIBRARY_FIXTURE_DIR: &str = "tests/fixtures/library-project";
+
+fn setup_test() {
+  let fixture_path = Path::new(LIBRARY_FIXTURE_DIR);
+  let _ = fs::remove_dir_all(fixture_path.join("target"));
+  let _ = fs::remove_dir_all(fixture_path.join("dist"));
+}
+
+/// Parses stdout, which must hold nothing but one JSON report per line.
+fn stdout_reports(stdout: &[u8]) -> Vec<Value> {
+  String::from_utf8(stdout.to_vec())
+    .unwrap()
+    .lines()
+    .map(|line| serde_json::from_str(line).unwrap_or_else(|e| panic!("{}: {:?}", e, line)))
+    .collect()
+}
+
+#[test]
+#[serial]
+fn test_json_report_describes_native_build() {
+  setup_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(LIBRARY_FIXTURE_DIR)
+    .args(["build", "--backend", "native", "--verify"])
+    .args(["--message-format", "json", "--report", "target/build.json"])
+    .assert()
+    .success();
+
+  let reports = stdout_reports(&assert.get_output().stdout);
+  assert_eq!(reports.len(), 1);
+  let report = &reports[0];
+  assert_eq!(report["name"], "library-project");
+  assert_eq!(report["version"], "0.1.0");
+  assert_eq!(report["release"], "1");
+  assert_eq!(report["backend"], "native");
+  assert_eq!(report["dry_run"], false);
+  assert!(report["spec"].is_null(), "the native backend renders no spec");
+  assert_eq!(report["commands"][0]["command"], "true");
+  assert!(report["commands"][0]["duration_secs"].is_f64());
+  assert_eq!(report["verification"][0]["package"], "library-project");
+  assert_eq!(report["verification"][0]["passed"], true);
+
+  let artifacts = report["artifacts"].as_array().unwrap();
+  assert_eq!(artifacts.len(), 1);
+  let rpm_path = Path::new(artifacts[0]["path"].as_str().unwrap());
+  assert!(rpm_path.parent().unwrap().ends_with("library-project/dist"));
+  let content = fs::read(rpm_path).unwrap();
+  assert_eq!(artifacts[0]["size"], content.len() as u64);
+  assert_eq!(artifacts[0]["sha256"], format!("{:x}", Sha256::digest(&content)));
+  assert_eq!(artifacts[0]["name"], "library-project");
+  assert_eq!(artifacts[0]["arch"], report["arch"]);
+
+  // The report file holds the same report.
+  let report_file = Path::new(LIBRARY_FIXTURE_DIR).join("target/build.json");
+  let file: Value = serde_json::from_str(&fs::read_to_string(report_file).unwrap()).unwrap();
+  assert_eq!(file["packages"].as_array().unwrap().len(), 1);
+  assert_eq!(&file["packages"][0], report);
+}
+
+#[test]
+#[serial]
+fn test_json_report_of_dry_run_keeps_stdout_clean() {
+  setup_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(LIBRARY_FIXTURE_DIR)
+    .args(["build", "--dry-run", "--message-format", "json"])
+    .assert()
+    .success();
+
+  // The rendered spec and the commands go to stderr instead.
+  let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+  assert!(stderr.contains("--- Dry Run Activated ---"));
+
+  let reports = stdout_reports(&assert.get_output().stdout);
+  assert_eq!(reports.len(), 1);
+  let report = &reports[0];
+  assert_eq!(report["backend"], "rpmbuild");
+  assert_eq!(report["dry_run"], true);
+  assert!(report["release"].is_null());
+  assert!(report["spec"].as_str().unwrap().ends_with("library-project-0.1.0.spec"));
+  assert!(report["archive"].is_null());
+  assert_eq!(report["commands"], Value::Array(Vec::new()));
+  assert_eq!(report["artifacts"], Value::Array(Vec::new()));
+  assert!(report["verification"].is_null());
+}
+
+#[test]
+#[serial]
+fn test_report_file_records_a_failed_build() {
+  setup_test();
+
+  let mut cmd = create_revolve_command();
+  cmd
+    .current_dir(LIBRARY_FIXTURE_DIR)
+    .args(["build", "--backend", "native", "--target", "wasm32-unknown-unknown"])
+    .args(["--report", "build.json"])
+    .assert()
+    .failure()
+    .code(3);
+
+  // The report is written next to the manifest, as the target directory may not exist.
+  let report_path = Path::new(LIBRARY_FIXTURE_DIR).join("build.json");
+  let contents = fs::read_to_string(&report_path).expect("the report file was not written");
+  let _ = fs::remove_file(&report_path);
+  let file: Value = serde_json::from_str(&contents).unwrap();
+  let report = &file["packages"][0];
+  assert_eq!(report["name"], "library-project");
+  assert_eq!(report["artifacts"], Value::Array(Vec::new()));
+  assert_eq!(report["error"]["exit_code"], 3);
+  let message = report["error"]["message"].as_str().unwrap();
+  assert!(message.contains("wasm32-unknown-unknown"), "{}", message);
+}
+
+#[test]
+#[serial]
+fn test_json_report_of_a_failed_build_uses_the_configured_target() {
+  let project_dir = std::env::temp_dir().join("cargo-revolve-report-failed");
+  let _ = fs::remove_dir_all(&project_dir);
+  copy_dir(Path::new(LIBRARY_FIXTURE_DIR), &project_dir);
+  let manifest_path = project_dir.join("Cargo.toml");
+  let manifest = fs::read_to_string(&manifest_path).unwrap().replace(
+    "build_command = \"true\"",
+    "build_command = \"false\"\ntarget = \"aarch64-unknown-linux-gnu\"",
+  );
+  fs::write(&manifest_path, manifest).unwrap();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(&project_dir)
+    .args(["build", "--backend", "native", "--message-format", "json"])
+    .assert()
+    .failure()
+    .code(7);
+  let _ = fs::remove_dir_all(&project_dir);
+
+  let reports = stdout_reports(&assert.get_output().stdout);
+  assert_eq!(reports.len(), 1, "{:?}", reports);
+  let report = &reports[0];
+  assert_eq!(report["name"], "library-project");
+  assert_eq!(report["arch"], "aarch64");
+  assert_eq!(report["error"]["exit_code"], 7);
+}
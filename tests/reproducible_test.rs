@@ -0,0 +1,135 @@
+mod common;
+
+use cargo_revolve::cargo_metadata::MetadataCommand;
+use cargo_revolve::{BuildOptions, PackagePlan, RevolveConfig, create_artifact_archive};
+use common::create_revolve_command;
+use rpm::Package;
+use serial_test::serial;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const SUBPACKAGE_FIXTURE_DIR: &str = "tests/fixtures/subpackage-project";
+
+fn setup_reproducible_test() {
+  let fixture_path = Path::new(SUBPACKAGE_FIXTURE_DIR);
+  let _ = fs::remove_dir_all(fixture_path.join("target"));
+  let _ = fs::remove_dir_all(fixture_path.join("dist"));
+}
+
+/// Builds the fixture in `--reproducible` mode and returns the SHA-256 of every RPM.
+fn build_reproducibly(backend: &str, source_date_epoch: &str) -> Vec<(String, String)> {
+  let _ = fs::remove_dir_all(Path::new(SUBPACKAGE_FIXTURE_DIR).join("dist"));
+  let mut cmd = create_revolve_command();
+  cmd
+    .current_dir(SUBPACKAGE_FIXTURE_DIR)
+    .env("SOURCE_DATE_EPOCH", source_date_epoch)
+    .args(["build", "--reproducible", "--backend", backend])
+    .assert()
+    .success();
+
+  let mut digests: Vec<(String, String)> =
+    fs::read_dir(Path::new(SUBPACKAGE_FIXTURE_DIR).join("dist"))
+      .unwrap()
+      .map(|e| e.unwrap().path())
+      .filter(|p| p.extension().is_some_and(|ext| ext == "rpm"))
+      .map(|p| {
+        let digest = Sha256::digest(fs::read(&p).unwrap());
+        (p.file_name().unwrap().to_string_lossy().into_owned(), format!("{:x}", digest))
+      })
+      .collect();
+  digests.sort();
+  digests
+}
+
+/// Writes the source archive of the fixture in `--reproducible` mode and returns its contents.
+/// Without `SOURCE_DATE_EPOCH`, the timestamp comes from the last git commit.
+fn write_reproducible_archive() -> Vec<u8> {
+  let fixture_path = Path::new(SUBPACKAGE_FIXTURE_DIR);
+  let metadata = MetadataCommand::new()
+    .manifest_path(fixture_path.join("Cargo.toml"))
+    .no_deps()
+    .exec()
+    .unwrap();
+  let package = metadata.root_package().unwrap();
+  let config = RevolveConfig::from_manifest(package.manifest_path.as_std_path()).unwrap();
+  let options = BuildOptions::new().reproducible(true);
+  let plan =
+    PackagePlan::new(&config, package, metadata.target_directory.as_std_path(), &options).unwrap();
+  fs::read(create_artifact_archive(&plan, false).unwrap()).unwrap()
+}
+
+/// The RPM of the main package, not one of the subpackages.
+fn main_rpm() -> PathBuf {
+  fs::read_dir(Path::new(SUBPACKAGE_FIXTURE_DIR).join("dist"))
+    .unwrap()
+    .map(|e| e.unwrap().path())
+    .find(|p| p.file_name().unwrap().to_string_lossy().starts_with("subpackage-project-0.3.0-"))
+    .expect("The main RPM was not built")
+}
+
+#[test]
+#[serial]
+fn test_reproducible_builds_are_identical() {
+  setup_reproducible_test();
+
+  let first = build_reproducibly("native", "1700000000");
+  // Let the clock move on so a stray timestamp would show up in the output.
+  std::thread::sleep(Duration::from_millis(1100));
+  let second = build_reproducibly("native", "1700000000");
+  assert_eq!(first.len(), 3);
+  assert_eq!(first, second);
+
+  let cli_rpm = first.iter().find(|(name, _)| name.starts_with("subpackage-project-cli-")).unwrap();
+  let metadata = Package::open(Path::new(SUBPACKAGE_FIXTURE_DIR).join("dist").join(&cli_rpm.0))
+    .unwrap()
+    .metadata;
+  assert_eq!(metadata.get_build_time().unwrap(), 1_700_000_000);
+  assert_eq!(metadata.get_build_host().unwrap(), "reproducible");
+
+  // The source archive does not depend on when it was written or on the mtimes of its sources.
+  let first_archive = write_reproducible_archive();
+  std::thread::sleep(Duration::from_millis(1100));
+  let guide = fs::File::options()
+    .write(true)
+    .open(Path::new(SUBPACKAGE_FIXTURE_DIR).join("docs/guide.md"))
+    .unwrap();
+  guide.set_modified(SystemTime::now()).unwrap();
+  let second_archive = write_reproducible_archive();
+  assert!(!first_archive.is_empty());
+  assert!(first_archive == second_archive, "the source archives differ");
+
+  if which::which("rpmbuild").is_err() {
+    println!("SKIPPING REST OF TEST: `rpmbuild` command not found in PATH.");
+    return;
+  }
+  let archive_path = Path::new(SUBPACKAGE_FIXTURE_DIR).join("target/subpackage-project-0.3.0.tar.gz");
+  let first = build_reproducibly("rpmbuild", "1700000000");
+  let first_archive = fs::read(&archive_path).unwrap();
+  std::thread::sleep(Duration::from_millis(1100));
+  let second = build_reproducibly("rpmbuild", "1700000000");
+  let second_archive = fs::read(&archive_path).unwrap();
+  assert!(first_archive == second_archive, "the source archives differ");
+  assert_eq!(first, second);
+}
+
+#[test]
+#[serial]
+fn test_source_date_epoch_changes_the_output() {
+  setup_reproducible_test();
+
+  let first = build_reproducibly("native", "1700000000");
+  let first_build_time = Package::open(main_rpm()).unwrap().metadata.get_build_time().unwrap();
+  let second = build_reproducibly("native", "1700086400");
+  let second_build_time = Package::open(main_rpm()).unwrap().metadata.get_build_time().unwrap();
+
+  assert_eq!(first.len(), 3);
+  assert_eq!(first_build_time, 1_700_000_000);
+  assert_eq!(second_build_time, 1_700_086_400);
+  // Same file names, but every RPM records the new timestamp.
+  for ((first_name, first_digest), (second_name, second_digest)) in first.iter().zip(&second) {
+    assert_eq!(first_name, second_name);
+    assert_ne!(first_digest, second_digest, "{} did not change", first_name);
+  }
+}
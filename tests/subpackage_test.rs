@@ -0,0 +1,93 @@
+mod common;
+
+use common::create_revolve_command;
+use rpm::Package;
+use serial_test::serial;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SUBPACKAGE_FIXTURE_DIR: &str = "tests/fixtures/subpackage-project";
+
+fn setup_subpackage_test() {
+  let fixture_path = Path::new(SUBPACKAGE_FIXTURE_DIR);
+  let _ = fs::remove_dir_all(fixture_path.join("target"));
+  let _ = fs::remove_dir_all(fixture_path.join("dist"));
+}
+
+/// Finds the RPM in `dist/` whose file name starts with `<name>-0.3.0-`.
+fn find_rpm(name: &str) -> PathBuf {
+  let prefix = format!("{}-0.3.0-", name);
+  fs::read_dir(Path::new(SUBPACKAGE_FIXTURE_DIR).join("dist"))
+    .expect("The 'dist' directory was not created")
+    .filter_map(|e| e.ok())
+    .map(|e| e.path())
+    .find(|p| p.file_name().unwrap().to_string_lossy().starts_with(&prefix))
+    .unwrap_or_else(|| panic!("No RPM found for {}", name))
+}
+
+#[test]
+#[serial]
+fn test_subpackages_in_dry_run() {
+  setup_subpackage_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(SUBPACKAGE_FIXTURE_DIR)
+    .arg("build")
+    .arg("--dry-run")
+    .assert()
+    .success();
+
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains("%package cli"));
+  assert!(output.contains("Requires:       subpackage-project = 0.3.0"));
+  assert!(output.contains("%files cli"));
+  assert!(output.contains("%package doc"));
+  assert!(output.contains("%dir /usr/share/doc/subpackage-project/"));
+  assert!(output.contains("/usr/share/doc/subpackage-project/guide.md"));
+}
+
+#[test]
+#[serial]
+fn test_subpackages_native_build_and_verify() {
+  setup_subpackage_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(SUBPACKAGE_FIXTURE_DIR)
+    .arg("build")
+    .arg("--backend")
+    .arg("native")
+    .arg("--verify")
+    .assert()
+    .success();
+
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains("Successfully built 3 RPM package(s)."));
+  assert_eq!(output.matches("Verification successful.").count(), 3);
+
+  let main_rpm = Package::open(find_rpm("subpackage-project")).unwrap();
+  let main_files = main_rpm.metadata.get_file_paths().unwrap();
+  assert_eq!(main_files, vec![PathBuf::from("/usr/bin/subpackage-project")]);
+
+  let cli_rpm = Package::open(find_rpm("subpackage-project-cli")).unwrap();
+  assert_eq!(cli_rpm.metadata.get_name().unwrap(), "subpackage-project-cli");
+  assert_eq!(
+    cli_rpm.metadata.get_summary().unwrap(),
+    "Command-line client for subpackage-project"
+  );
+  let requires = cli_rpm.metadata.get_requires().unwrap();
+  assert!(
+    requires
+      .iter()
+      .any(|d| d.name == "subpackage-project" && d.version == "0.3.0"),
+    "cli subpackage is missing its Requires on the main package: {:?}",
+    requires
+  );
+
+  let doc_rpm = Package::open(find_rpm("subpackage-project-doc")).unwrap();
+  let doc_files = doc_rpm.metadata.get_file_paths().unwrap();
+  assert!(doc_files.contains(&PathBuf::from("/usr/share/doc/subpackage-project/README.md")));
+  assert!(doc_files.contains(&PathBuf::from("/usr/share/doc/subpackage-project/guide.md")));
+  assert!(!doc_files.contains(&PathBuf::from("/usr/bin/subpackage-cli")));
+}
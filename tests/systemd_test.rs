@@ -0,0 +1,132 @@
+mod common;
+
+use common::{create_revolve_command, find_rpm};
+use rpm::Package;
+use serial_test::serial;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SYSTEMD_FIXTURE_DIR: &str = "tests/fixtures/systemd-project";
+const BROKEN_UNIT_FIXTURE_DIR: &str = "tests/fixtures/broken-unit-project";
+
+fn setup_systemd_test() {
+  let fixture_path = Path::new(SYSTEMD_FIXTURE_DIR);
+  let _ = fs::remove_dir_all(fixture_path.join("target"));
+  let _ = fs::remove_dir_all(fixture_path.join("dist"));
+}
+
+#[test]
+#[serial]
+fn test_systemd_units_in_dry_run() {
+  setup_systemd_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(SYSTEMD_FIXTURE_DIR)
+    .arg("build")
+    .arg("--dry-run")
+    .assert()
+    .success();
+
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains("BuildRequires:  systemd-rpm-macros"));
+  assert!(output.contains("%systemd_post systemd-project.service systemd-project.socket"));
+  assert!(output.contains("%systemd_preun systemd-project.service systemd-project.socket"));
+  assert!(output.contains(
+    "%systemd_postun_with_restart systemd-project.service systemd-project.socket"
+  ));
+  assert!(output.contains("/usr/lib/systemd/system/systemd-project.socket"));
+}
+
+#[test]
+#[serial]
+fn test_systemd_units_native_build_and_verify() {
+  setup_systemd_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(SYSTEMD_FIXTURE_DIR)
+    .args(["build", "--backend", "native", "--verify"])
+    .assert()
+    .success();
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains("Verification successful."));
+
+  let metadata = Package::open(find_rpm(&Path::new(SYSTEMD_FIXTURE_DIR).join("dist"))).unwrap().metadata;
+  let files = metadata.get_file_paths().unwrap();
+  assert!(files.contains(&PathBuf::from("/usr/lib/systemd/system/systemd-project.service")));
+  assert!(files.contains(&PathBuf::from("/usr/lib/systemd/system/systemd-project.socket")));
+  assert!(
+    !files.contains(&PathBuf::from("/usr/lib/systemd/system")),
+    "The package must not own the unit directory"
+  );
+
+  let post = metadata.get_post_install_script().unwrap().script;
+  assert!(post.contains("systemctl --no-reload preset systemd-project.service systemd-project.socket"));
+  let preun = metadata.get_pre_uninstall_script().unwrap().script;
+  assert!(preun.contains("disable --now systemd-project.service"));
+  let postun = metadata.get_post_uninstall_script().unwrap().script;
+  assert!(postun.contains("try-restart systemd-project.service"));
+}
+
+#[test]
+#[serial]
+fn test_system_users_from_sysusers_file() {
+  setup_systemd_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(SYSTEMD_FIXTURE_DIR)
+    .arg("build")
+    .arg("--dry-run")
+    .assert()
+    .success();
+  let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(output.contains("%sysusers_create_compat "));
+  assert!(output.contains("revolve-generated/systemd-project/systemd-project.conf\n"));
+  assert!(output.contains("/usr/lib/sysusers.d/systemd-project.conf"));
+  // A dry run does not write the generated file.
+  let generated_path = Path::new(SYSTEMD_FIXTURE_DIR)
+    .join("target/revolve-generated/systemd-project/systemd-project.conf");
+  assert!(!generated_path.exists());
+
+  let mut cmd = create_revolve_command();
+  cmd
+    .current_dir(SYSTEMD_FIXTURE_DIR)
+    .args(["build", "--backend", "native", "--verify"])
+    .assert()
+    .success();
+
+  let generated = fs::read_to_string(&generated_path).unwrap();
+  assert!(generated.contains("u systemd-project - \"systemd-project\" /var/lib/systemd-project /sbin/nologin\n"));
+  assert!(generated.contains("u sdp-worker -:sdp-jobs \"sdp-worker\" - /bin/sh\n"));
+  assert!(generated.contains("u sdp-cron -:sdp-jobs \"sdp-cron\" - /sbin/nologin\n"));
+  // The group both workers share is only declared once, before the users.
+  assert_eq!(generated.matches("g sdp-jobs -\n").count(), 1, "{}", generated);
+  assert!(generated.find("g sdp-jobs").unwrap() < generated.find("u sdp-worker").unwrap());
+
+  let metadata = Package::open(find_rpm(&Path::new(SYSTEMD_FIXTURE_DIR).join("dist"))).unwrap().metadata;
+  let files = metadata.get_file_paths().unwrap();
+  assert!(files.contains(&PathBuf::from("/usr/lib/sysusers.d/systemd-project.conf")));
+  let pre = metadata.get_pre_install_script().unwrap().script;
+  assert!(pre.contains("getent passwd sdp-worker >/dev/null || useradd -r -g sdp-jobs"));
+  assert!(pre.contains("getent passwd sdp-cron >/dev/null || useradd -r -g sdp-jobs"));
+  assert_eq!(pre.matches("groupadd -r sdp-jobs\n").count(), 1, "{}", pre);
+}
+
+#[test]
+#[serial]
+fn test_unit_without_service_section_is_rejected() {
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(BROKEN_UNIT_FIXTURE_DIR)
+    .arg("build")
+    .arg("--dry-run")
+    .assert()
+    .failure()
+    .code(3);
+
+  let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+  assert!(stderr.contains("Invalid systemd unit"));
+  assert!(stderr.contains("a .service unit must have a [Service] section"));
+}
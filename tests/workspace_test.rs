@@ -0,0 +1,124 @@
+mod common;
+
+use common::create_revolve_command;
+use rpm::Package;
+use serial_test::serial;
+use std::fs;
+use std::path::Path;
+
+const WORKSPACE_FIXTURE_DIR: &str = "tests/fixtures/workspace-project";
+const MEMBERS: [&str; 3] = ["service-a", "service-b", "broken"];
+
+/// Helper to clean the workspace and every member between runs. Also writes the man page that
+/// service-a picks up with a glob in the workspace target directory.
+fn setup_workspace_test() {
+  let fixture_path = Path::new(WORKSPACE_FIXTURE_DIR);
+  let _ = fs::remove_dir_all(fixture_path.join("target"));
+  for member in MEMBERS {
+    let _ = fs::remove_dir_all(fixture_path.join(member).join("target"));
+    let _ = fs::remove_dir_all(fixture_path.join(member).join("dist"));
+  }
+  fs::create_dir_all(fixture_path.join("target/man")).unwrap();
+  fs::write(fixture_path.join("target/man/service-a.1"), ".TH SERVICE-A 1\n").unwrap();
+}
+
+/// Returns the RPM file names found in a member's `dist` directory.
+fn member_rpms(member: &str) -> Vec<String> {
+  let dist_dir = Path::new(WORKSPACE_FIXTURE_DIR).join(member).join("dist");
+  fs::read_dir(dist_dir)
+    .map(|entries| {
+      entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".rpm"))
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+#[test]
+#[serial]
+fn test_workspace_build_reports_all_failures_together() {
+  // Uses the native backend, so this test does not require rpmbuild.
+  setup_workspace_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(WORKSPACE_FIXTURE_DIR)
+    .arg("build")
+    .arg("--workspace")
+    .arg("--backend")
+    .arg("native")
+    .assert()
+    .failure()
+    // The broken member's missing asset decides the exit code.
+    .code(5);
+
+  let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+
+  // The broken member must not stop the healthy members from being packaged.
+  assert_eq!(member_rpms("service-a").len(), 1, "service-a RPM was not built");
+  assert_eq!(member_rpms("service-b").len(), 1, "service-b RPM was not built");
+
+  assert!(stdout.contains("Build summary:"));
+  assert!(stdout.lines().any(|l| l.contains("broken") && l.contains("FAILED")));
+  assert!(stdout.lines().any(|l| l.contains("service-a") && l.contains("ok")));
+  assert!(stdout.lines().any(|l| l.contains("service-b") && l.contains("ok")));
+  // Members without a revolve table are skipped entirely.
+  assert!(!stdout.contains("tools"));
+
+  assert!(stderr.contains("Error building broken"));
+  assert!(stderr.contains("1 of 3 package(s) failed to build."));
+}
+
+#[test]
+#[serial]
+fn test_workspace_build_selected_package() {
+  setup_workspace_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(WORKSPACE_FIXTURE_DIR)
+    .arg("build")
+    .arg("-p")
+    .arg("service-a")
+    .arg("--backend")
+    .arg("native")
+    .arg("--verify")
+    .assert()
+    .success();
+
+  let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+  assert!(stdout.contains("Verification successful."));
+
+  let rpms = member_rpms("service-a");
+  assert_eq!(rpms.len(), 1, "service-a RPM was not built");
+  assert!(member_rpms("service-b").is_empty(), "service-b should not have been built");
+
+  // The `target/man/*.1` glob is expanded in the workspace target directory, not the member's.
+  let rpm_path = Path::new(WORKSPACE_FIXTURE_DIR).join("service-a/dist").join(&rpms[0]);
+  let package = Package::open(&rpm_path).unwrap();
+  let paths: Vec<_> = package.metadata.get_file_paths().unwrap();
+  assert!(paths.iter().any(|p| p == Path::new("/usr/share/man/man1/service-a.1")), "{:?}", paths);
+}
+
+#[test]
+#[serial]
+fn test_workspace_build_unknown_package() {
+  setup_workspace_test();
+
+  let mut cmd = create_revolve_command();
+  let assert = cmd
+    .current_dir(WORKSPACE_FIXTURE_DIR)
+    .arg("build")
+    .arg("-p")
+    .arg("does-not-exist")
+    .arg("--dry-run")
+    .assert()
+    .failure()
+    .code(3);
+
+  let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+  assert!(stderr.contains("Package `does-not-exist` is not a member of this workspace"));
+}